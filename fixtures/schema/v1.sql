@@ -0,0 +1,13 @@
+-- schema as created before numeric markets, details got a row per observation
+CREATE TABLE log (time DATETIME DEFAULT CURRENT_TIMESTAMP, type TEXT, content TEXT);
+INSERT INTO log (type, content) VALUES ("creation", "hello world");
+INSERT INTO log (time, type, content) VALUES ("2025-11-01 10:00:00", "pub", "Manifold a");
+CREATE TABLE probabilities(time DATETIME DEFAULT CURRENT_TIMESTAMP, platform TEXT, id TEXT, prob REAL);
+CREATE INDEX idx_probabilities_platform_id_time ON probabilities(platform, id, time);
+INSERT INTO probabilities VALUES ("2025-11-01 08:00:00", "Manifold", "a", 0.2);
+INSERT INTO probabilities VALUES ("2025-11-01 09:00:00", "Manifold", "a", 0.6);
+INSERT INTO probabilities VALUES ("2025-11-01 09:00:00", "Metaculus", "b", 0.4);
+CREATE TABLE details (platform TEXT, id TEXT, title TEXT, url TEXT);
+INSERT INTO details VALUES ("Manifold", "a", "Old title", "https://manifold.markets/a");
+INSERT INTO details VALUES ("Manifold", "a", "New title", "https://manifold.markets/a");
+INSERT INTO details VALUES ("Metaculus", "b", "Other", "https://www.metaculus.com/questions/b");
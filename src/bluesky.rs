@@ -0,0 +1,184 @@
+use crate::http::{HttpClient, HttpError, Request};
+use crate::model::Change;
+use crate::publisher::{fit, Publisher};
+use chrono::SecondsFormat;
+use json::{array, object, JsonValue};
+use log::*;
+use std::sync::Arc;
+
+/// Posts via the AT Protocol, logging in with an app password
+pub struct Bluesky {
+    http: Arc<dyn HttpClient>,
+    name: String,
+    /// Like https://bsky.social
+    service: String,
+    handle: String,
+    app_password: String,
+    /// Longest post the service accepts, in characters
+    max_length: usize,
+}
+
+impl Bluesky {
+    pub fn new(
+        http: Arc<dyn HttpClient>,
+        name: String,
+        service: String,
+        handle: String,
+        app_password: String,
+        max_length: usize,
+    ) -> Self {
+        Bluesky {
+            http,
+            name,
+            service: service.trim_end_matches('/').to_string(),
+            handle,
+            app_password,
+            max_length,
+        }
+    }
+
+    /// Returns the access token and the DID of the account
+    fn create_session(&self) -> Result<(String, String), HttpError> {
+        let url = format!("{}/xrpc/com.atproto.server.createSession", self.service);
+        let request = Request::post(url.as_str())
+            .header("Accept", "application/json")
+            .json(&object! {
+                identifier: self.handle.as_str(),
+                password: self.app_password.as_str(),
+            });
+        let response = self.http.send(&request)?;
+        let j = json::parse(response.body.as_str())
+            .map_err(|e| HttpError::Transport(format!("bad session: {}", e)))?;
+        match (j["accessJwt"].as_str(), j["did"].as_str()) {
+            (Some(token), Some(did)) => Ok((token.to_string(), did.to_string())),
+            _ => Err(HttpError::Transport("session without token".to_string())),
+        }
+    }
+}
+
+impl Publisher for Bluesky {
+    fn target(&self) -> &str {
+        &self.name
+    }
+
+    fn publish(&self, change: &Change) -> Result<Option<String>, HttpError> {
+        let (token, did) = self.create_session()?;
+        let text = fit(&change.to_string(), self.max_length);
+        let record = object! {
+            "$type": "app.bsky.feed.post",
+            text: text.as_str(),
+            createdAt: change.time().to_rfc3339_opts(SecondsFormat::Millis, true),
+            langs: array!["en"],
+            facets: facets(&text, change.url()),
+            embed: object! {
+                "$type": "app.bsky.embed.external",
+                external: object! {
+                    uri: change.url(),
+                    title: change.title(),
+                    description: text.lines().next().unwrap_or(""),
+                },
+            },
+        };
+        let url = format!("{}/xrpc/com.atproto.repo.createRecord", self.service);
+        let request = Request::post(url.as_str())
+            .header("Accept", "application/json")
+            .header("Authorization", format!("Bearer {}", token).as_str())
+            .json(&object! {
+                repo: did.as_str(),
+                collection: "app.bsky.feed.post",
+                record: record,
+            });
+        match self.http.send(&request) {
+            Ok(response) => Ok(json::parse(response.body.as_str())
+                .ok()
+                .and_then(|j| j["uri"].as_str().map(str::to_string))),
+            Err(e) => {
+                debug!("bluesky post failed: {}", e);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Rich text annotations for the link and the hashtags in the last line.
+/// Positions count UTF-8 bytes, not characters.
+fn facets(text: &str, url: &str) -> JsonValue {
+    let facet = |start: usize, end: usize, feature: JsonValue| {
+        object! {
+            index: object! { byteStart: start, byteEnd: end },
+            features: array![feature],
+        }
+    };
+    let mut facets = JsonValue::new_array();
+    let last_line = text.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    if let Some(start) = text[last_line..].find(url).filter(|_| !url.is_empty()) {
+        let start = last_line + start;
+        let link = object! { "$type": "app.bsky.richtext.facet#link", uri: url };
+        let _ = facets.push(facet(start, start + url.len(), link));
+    }
+    let mut start = last_line;
+    for word in text[last_line..].split(' ') {
+        if let Some(tag) = word.strip_prefix('#').filter(|t| !t.is_empty()) {
+            let tag = object! { "$type": "app.bsky.richtext.facet#tag", tag: tag };
+            let _ = facets.push(facet(start, start + word.len(), tag));
+        }
+        start += word.len() + 1;
+    }
+    facets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock;
+    use crate::http::UreqClient;
+    use crate::model::DiffDuration;
+
+    #[test]
+    fn post_with_facets() {
+        let (address, requests) = mock::serve(vec![
+            (200, r#"{"accessJwt": "token", "did": "did:plc:abc"}"#),
+            (
+                200,
+                r#"{"uri": "at://did:plc:abc/app.bsky.feed.post/1", "cid": "x"}"#,
+            ),
+        ]);
+        let bluesky = Bluesky::new(
+            Arc::new(UreqClient::new()),
+            "bluesky".to_string(),
+            address + "/",
+            "news.example.org".to_string(),
+            "app-password".to_string(),
+            300,
+        );
+        let change = Change::new_from05(DiffDuration::day(), 0.75);
+        let receipt = bluesky.publish(&change).expect("post");
+        assert_eq!(
+            receipt.as_deref(),
+            Some("at://did:plc:abc/app.bsky.feed.post/1")
+        );
+        let (path, login) = requests.recv().expect("login");
+        assert_eq!(path, "/xrpc/com.atproto.server.createSession");
+        assert_eq!(login["identifier"], "news.example.org");
+        assert_eq!(login["password"], "app-password");
+        let (path, post) = requests.recv().expect("post");
+        assert_eq!(path, "/xrpc/com.atproto.repo.createRecord");
+        assert_eq!(post["repo"], "did:plc:abc");
+        let record = &post["record"];
+        let text = record["text"].as_str().expect("text");
+        assert_eq!(text, "+25% in a day 📈 title\nurl #prediction #platform");
+        let facets = &record["facets"];
+        assert_eq!(facets.len(), 3);
+        // the emoji takes four bytes
+        assert_eq!(facets[0]["index"]["byteStart"], 25);
+        assert_eq!(facets[0]["features"][0]["uri"], "url");
+        assert_eq!(facets[1]["index"]["byteStart"], 29);
+        assert_eq!(facets[1]["index"]["byteEnd"], 40);
+        assert_eq!(facets[1]["features"][0]["tag"], "prediction");
+        assert_eq!(facets[2]["features"][0]["tag"], "platform");
+        let card = &record["embed"]["external"];
+        assert_eq!(card["uri"], "url");
+        assert_eq!(card["title"], "title");
+        assert_eq!(card["description"], "+25% in a day 📈 title");
+    }
+}
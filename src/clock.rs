@@ -0,0 +1,53 @@
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Mutex;
+
+/// Source of the current time for everything which depends on it
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The real time
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Time only moves when told to, for tests and replays
+pub struct ManualClock {
+    time: Mutex<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    pub fn new(time: DateTime<Utc>) -> Self {
+        ManualClock {
+            time: Mutex::new(time),
+        }
+    }
+
+    pub fn advance(&self, d: Duration) {
+        *self.time.lock().unwrap() += d;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.time.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual() {
+        let start = Utc::now();
+        let clock = ManualClock::new(start);
+        assert_eq!(clock.now(), start);
+        clock.advance(Duration::hours(2));
+        assert_eq!(clock.now() - start, Duration::hours(2));
+    }
+}
@@ -0,0 +1,550 @@
+use log::*;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Method::Get => write!(f, "GET"),
+            Method::Post => write!(f, "POST"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+impl Request {
+    pub fn get(url: &str) -> Self {
+        Request {
+            method: Method::Get,
+            url: url.to_string(),
+            headers: vec![],
+            body: None,
+        }
+    }
+
+    pub fn post(url: &str) -> Self {
+        Request {
+            method: Method::Post,
+            url: url.to_string(),
+            headers: vec![],
+            body: None,
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn form(self, pairs: &[(&str, &str)]) -> Self {
+        let body = pairs
+            .iter()
+            .map(|(k, v)| format!("{}={}", form_encode(k), form_encode(v)))
+            .collect::<Vec<String>>()
+            .join("&");
+        let mut r = self.header("Content-Type", "application/x-www-form-urlencoded");
+        r.body = Some(body);
+        r
+    }
+
+    pub fn json(self, body: &json::JsonValue) -> Self {
+        let mut r = self.header("Content-Type", "application/json");
+        r.body = Some(body.dump());
+        r
+    }
+}
+
+fn form_encode(s: &str) -> String {
+    let mut ret = String::new();
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'*' => {
+                ret.push(b as char)
+            }
+            b' ' => ret.push('+'),
+            _ => ret.push_str(format!("%{:02X}", b).as_str()),
+        }
+    }
+    ret
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug)]
+pub enum HttpError {
+    /// Server answered with an error status
+    Status(Response),
+    /// Connection, TLS and similar failures
+    Transport(String),
+    /// Missing or broken recording, retrying cannot help
+    Recording(String),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HttpError::Status(r) => write!(f, "status {}: {}", r.status, r.body),
+            HttpError::Transport(e) => write!(f, "transport: {}", e),
+            HttpError::Recording(e) => write!(f, "recording: {}", e),
+        }
+    }
+}
+
+/// Everything which talks to the network goes through this,
+/// so it can be recorded and replayed.
+pub trait HttpClient: Send + Sync {
+    fn send(&self, request: &Request) -> Result<Response, HttpError>;
+}
+
+pub struct UreqClient {
+    agent: ureq::Agent,
+}
+
+impl UreqClient {
+    pub fn new() -> Self {
+        UreqClient {
+            agent: ureq::Agent::new(),
+        }
+    }
+}
+
+impl HttpClient for UreqClient {
+    fn send(&self, request: &Request) -> Result<Response, HttpError> {
+        let mut r = match request.method {
+            Method::Get => self.agent.get(request.url.as_str()),
+            Method::Post => self.agent.post(request.url.as_str()),
+        };
+        for (name, value) in &request.headers {
+            r = r.set(name, value);
+        }
+        let call = match &request.body {
+            Some(body) => r.send_string(body),
+            None => r.call(),
+        };
+        match call {
+            Ok(response) => read_response(response),
+            Err(ureq::Error::Status(_code, response)) => {
+                Err(HttpError::Status(read_response(response)?))
+            }
+            Err(e) => Err(HttpError::Transport(e.to_string())),
+        }
+    }
+}
+
+fn read_response(r: ureq::Response) -> Result<Response, HttpError> {
+    let status = r.status();
+    let headers = r
+        .headers_names()
+        .iter()
+        .filter_map(|name| Some((name.clone(), r.header(name)?.to_string())))
+        .collect();
+    let body = r
+        .into_string()
+        .map_err(|e| HttpError::Transport(e.to_string()))?;
+    Ok(Response {
+        status,
+        headers,
+        body,
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub retries: u32,
+    /// Wait before the first retry, doubled for each further one
+    pub backoff: Duration,
+    /// Never wait longer than this, even if the server asks for it
+    pub max_wait: Duration,
+    /// Keep at least this much time between two requests
+    pub min_interval: Duration,
+    /// No retry may end after this
+    pub deadline: Option<Instant>,
+}
+
+/// Retries on rate limiting, server errors and transport errors
+/// and spaces requests out to stay below a request rate.
+pub struct Retrying {
+    inner: Arc<dyn HttpClient>,
+    policy: RetryPolicy,
+    next_slot: Mutex<Instant>,
+    sleep: fn(Duration),
+}
+
+impl Retrying {
+    pub fn new(inner: Arc<dyn HttpClient>, policy: RetryPolicy) -> Self {
+        Retrying {
+            inner,
+            policy,
+            next_slot: Mutex::new(Instant::now()),
+            sleep: thread::sleep,
+        }
+    }
+
+    fn wait_for_slot(&self) {
+        let wait = {
+            let mut next = self.next_slot.lock().expect("rate limit lock");
+            let now = Instant::now();
+            let slot = (*next).max(now);
+            *next = slot + self.policy.min_interval;
+            slot - now
+        };
+        if !wait.is_zero() {
+            (self.sleep)(wait);
+        }
+    }
+
+    /// How long to wait before retrying, None if retrying is pointless
+    fn retry_wait(&self, result: &Result<Response, HttpError>, attempt: u32) -> Option<Duration> {
+        let backoff = 2u32
+            .checked_pow(attempt)
+            .and_then(|factor| self.policy.backoff.checked_mul(factor))
+            .unwrap_or(self.policy.max_wait);
+        let wait = match result {
+            Ok(_) => return None,
+            Err(HttpError::Transport(_)) => backoff,
+            Err(HttpError::Status(r)) if r.status == 429 || r.status >= 500 => {
+                match r
+                    .header("Retry-After")
+                    .and_then(|v| v.trim().parse::<u64>().ok())
+                {
+                    Some(seconds) => Duration::from_secs(seconds),
+                    None => backoff,
+                }
+            }
+            Err(HttpError::Status(_)) | Err(HttpError::Recording(_)) => return None,
+        };
+        Some(wait.min(self.policy.max_wait))
+    }
+}
+
+impl HttpClient for Retrying {
+    fn send(&self, request: &Request) -> Result<Response, HttpError> {
+        let mut attempt = 0;
+        loop {
+            self.wait_for_slot();
+            let result = self.inner.send(request);
+            if attempt >= self.policy.retries {
+                return result;
+            }
+            let deadline = self.policy.deadline;
+            match self.retry_wait(&result, attempt) {
+                Some(wait) if deadline.is_some_and(|d| Instant::now() + wait > d) => {
+                    return result;
+                }
+                Some(wait) => {
+                    if let Err(e) = &result {
+                        info!("retry {} in {:?} after {}", request.url, wait, e);
+                    }
+                    (self.sleep)(wait);
+                }
+                None => return result,
+            }
+            attempt += 1;
+        }
+    }
+}
+
+/// Passes requests on and stores each response as a file
+pub struct Recorder {
+    inner: Box<dyn HttpClient>,
+    dir: PathBuf,
+}
+
+impl Recorder {
+    pub fn new(inner: Box<dyn HttpClient>, dir: PathBuf) -> Self {
+        Recorder { inner, dir }
+    }
+}
+
+impl HttpClient for Recorder {
+    fn send(&self, request: &Request) -> Result<Response, HttpError> {
+        let result = self.inner.send(request);
+        let response = match &result {
+            Ok(r) => r,
+            Err(HttpError::Status(r)) => r,
+            Err(HttpError::Transport(_)) | Err(HttpError::Recording(_)) => return result,
+        };
+        let path = self.dir.join(recording_name(request));
+        match fs::create_dir_all(&self.dir).and_then(|_| fs::write(&path, dump(response))) {
+            Ok(_) => debug!("recorded {} {} as {:?}", request.method, request.url, path),
+            Err(e) => warn!("failed to record {:?}: {}", path, e),
+        }
+        result
+    }
+}
+
+/// Answers requests from files stored by a Recorder
+pub struct Replay {
+    dir: PathBuf,
+}
+
+impl Replay {
+    pub fn new(dir: PathBuf) -> Self {
+        Replay { dir }
+    }
+}
+
+impl HttpClient for Replay {
+    fn send(&self, request: &Request) -> Result<Response, HttpError> {
+        let path = self.dir.join(recording_name(request));
+        let content = fs::read_to_string(&path).map_err(|e| {
+            HttpError::Recording(format!(
+                "no recording {:?} for {}: {}",
+                path, request.url, e
+            ))
+        })?;
+        let response = undump(&content)
+            .ok_or_else(|| HttpError::Recording(format!("broken recording {:?}", path)))?;
+        if response.status >= 400 {
+            Err(HttpError::Status(response))
+        } else {
+            Ok(response)
+        }
+    }
+}
+
+/// File name for a request, readable but unique through a hash.
+/// Headers are not part of it, so access tokens do not matter.
+fn recording_name(request: &Request) -> String {
+    let key = format!(
+        "{} {} {}",
+        request.method,
+        request.url,
+        request.body.as_deref().unwrap_or("")
+    );
+    let readable: String = request
+        .url
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .take(60)
+        .collect();
+    format!(
+        "{}-{}-{:016x}.http",
+        request.method.to_string().to_lowercase(),
+        readable,
+        fnv1a(key.as_bytes())
+    )
+}
+
+/// Stable across Rust versions, unlike the std hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Status line, header lines, empty line, body
+fn dump(r: &Response) -> String {
+    let mut s = format!("{}\n", r.status);
+    for (name, value) in &r.headers {
+        s.push_str(format!("{}: {}\n", name, value).as_str());
+    }
+    s.push('\n');
+    s.push_str(r.body.as_str());
+    s
+}
+
+fn undump(s: &str) -> Option<Response> {
+    let (head, body) = s.split_once("\n\n")?;
+    let mut lines = head.lines();
+    let status = lines.next()?.trim().parse::<u16>().ok()?;
+    let headers = lines
+        .filter_map(|l| l.split_once(": "))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    Some(Response {
+        status,
+        headers,
+        body: body.to_string(),
+    })
+}
+
+/// A local HTTP server for testing clients of other APIs
+#[cfg(test)]
+pub mod mock {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Answers requests with the given statuses and JSON bodies in order.
+    /// Passes on the path and the JSON body of each request.
+    pub fn serve(
+        answers: Vec<(u16, &'static str)>,
+    ) -> (String, mpsc::Receiver<(String, json::JsonValue)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let address = format!("http://{}", listener.local_addr().expect("address"));
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for (stream, (status, answer)) in listener.incoming().zip(answers) {
+                let mut stream = stream.expect("connection");
+                let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).expect("request line");
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("header");
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().expect("length");
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).expect("body");
+                let path = request_line.split(' ').nth(1).unwrap_or("").to_string();
+                let body = String::from_utf8(body).expect("utf8");
+                sender
+                    .send((path, json::parse(&body).expect("json")))
+                    .expect("send");
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    answer.len(),
+                    answer
+                )
+                .expect("answer");
+            }
+        });
+        (address, receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed;
+
+    impl HttpClient for Fixed {
+        fn send(&self, request: &Request) -> Result<Response, HttpError> {
+            Ok(Response {
+                status: 200,
+                headers: vec![("Content-Type".to_string(), "text/plain".to_string())],
+                body: format!("hello {}\n\nbye", request.url),
+            })
+        }
+    }
+
+    /// Answers with the given statuses in order
+    struct Flaky {
+        statuses: Mutex<Vec<u16>>,
+    }
+
+    impl HttpClient for Flaky {
+        fn send(&self, _request: &Request) -> Result<Response, HttpError> {
+            let status = self.statuses.lock().expect("lock").remove(0);
+            let response = Response {
+                status,
+                headers: vec![("Retry-After".to_string(), "7".to_string())],
+                body: String::new(),
+            };
+            match status {
+                0 => Err(HttpError::Transport("connection reset".to_string())),
+                200 => Ok(response),
+                _ => Err(HttpError::Status(response)),
+            }
+        }
+    }
+
+    fn retrying(statuses: Vec<u16>) -> Retrying {
+        let policy = RetryPolicy {
+            retries: 3,
+            backoff: Duration::from_secs(1),
+            max_wait: Duration::from_secs(60),
+            min_interval: Duration::ZERO,
+            deadline: None,
+        };
+        let flaky = Flaky {
+            statuses: Mutex::new(statuses),
+        };
+        let mut r = Retrying::new(Arc::new(flaky), policy);
+        r.sleep = |_| {};
+        r
+    }
+
+    #[test]
+    fn retry_waits() {
+        let r = retrying(vec![0, 503, 429, 200]);
+        let ok = Ok(Response {
+            status: 200,
+            headers: vec![],
+            body: String::new(),
+        });
+        assert_eq!(r.retry_wait(&ok, 0), None);
+        let request = Request::get("https://example.org/");
+        let transport = r.inner.send(&request);
+        assert_eq!(r.retry_wait(&transport, 0), Some(Duration::from_secs(1)));
+        assert_eq!(r.retry_wait(&transport, 40), Some(Duration::from_secs(60)));
+        let missing = Err(HttpError::Recording("no recording".to_string()));
+        assert_eq!(r.retry_wait(&missing, 0), None);
+        let unavailable = r.inner.send(&request);
+        assert_eq!(r.retry_wait(&unavailable, 2), Some(Duration::from_secs(7)));
+        let r = retrying(vec![0, 503, 429, 200]);
+        assert!(r.send(&request).is_ok());
+        let r = retrying(vec![404, 200]);
+        assert!(r.send(&request).is_err()); // no point in retrying
+        let r = retrying(vec![500, 500, 500, 500, 200]);
+        assert!(r.send(&request).is_err()); // out of retries
+        let mut r = retrying(vec![503, 200]);
+        r.policy.deadline = Some(Instant::now());
+        assert!(r.send(&request).is_err()); // out of time
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let dir = std::env::temp_dir().join(format!("mrktws-http-{}", std::process::id()));
+        let request = Request::post("https://example.org/x?y=1").form(&[("status", "a b&c")]);
+        assert_eq!(request.body.as_deref(), Some("status=a+b%26c"));
+        let recorded = Recorder::new(Box::new(Fixed), dir.clone())
+            .send(&request)
+            .expect("recorded");
+        let replayed = Replay::new(dir.clone()).send(&request).expect("replayed");
+        assert_eq!(recorded, replayed);
+        let other = Request::post("https://example.org/x?y=1").form(&[("status", "d")]);
+        assert!(Replay::new(dir.clone()).send(&other).is_err());
+        fs::remove_dir_all(dir).ok();
+    }
+}
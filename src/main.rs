@@ -1,12 +1,30 @@
+mod bluesky;
+mod clock;
+mod http;
 mod mastodon;
 mod model;
 mod platforms;
+mod pool;
+mod publisher;
+mod ranking;
+mod telegram;
+use crate::bluesky::Bluesky;
+use crate::clock::{Clock, SystemClock};
+use crate::http::*;
 use crate::mastodon::Mastodon;
 use crate::model::*;
 use crate::platforms::*;
+use crate::pool::bounded_map;
+use crate::publisher::Publisher;
+use crate::ranking::*;
+use crate::telegram::Telegram;
 use clap::{Arg, ArgAction, Command};
 use ini::Ini;
 use log::*;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 fn arguments() -> Command {
     Command::new("marketwise-news")
@@ -23,6 +41,25 @@ fn arguments() -> Command {
                 .action(ArgAction::SetFalse)
                 .help("do not publish noteworthy change"),
         )
+        .arg(
+            Arg::new("compact")
+                .long("compact")
+                .action(ArgAction::SetTrue)
+                .help("downsample old probabilities according to [retention]"),
+        )
+        .arg(
+            Arg::new("record")
+                .long("record")
+                .value_name("DIR")
+                .help("store all HTTP responses of the fetchers in this directory"),
+        )
+        .arg(
+            Arg::new("replay")
+                .long("replay")
+                .value_name("DIR")
+                .conflicts_with("record")
+                .help("answer HTTP requests from recorded responses instead of the network"),
+        )
         .arg(
             Arg::new("ini")
                 .long("config-file")
@@ -30,9 +67,14 @@ fn arguments() -> Command {
                 .default_value("mrktws.ini")
                 .help("ini config file"),
         )
+        .subcommand(
+            Command::new("backtest").about(
+                "print what would have been published, hour by hour, from the stored history",
+            ),
+        )
 }
 
-fn main() {
+fn main() -> ExitCode {
     env_logger::init();
     info!("main start");
 
@@ -40,71 +82,272 @@ fn main() {
     let ini_path = args.get_one::<String>("ini").expect("ini");
     let config = Ini::load_from_file(ini_path.as_str()).ok();
 
-    let db = get_model(&config);
-    db.transact(&|| {
-        let platforms: Vec<Box<dyn PlatformAPI>> = match args.get_flag("get_some") {
-            true => {
-                vec![
-                    Metaculus::new_boxed(
-                        get_fetch_limit(&config, "metaculus", 100),
-                        get_access_token(&config),
-                    ),
-                    Polymarket::new_boxed(get_fetch_limit(&config, "polymarket", 100)),
-                    Manifold::new_boxed(get_fetch_limit(&config, "manifold", 100)),
-                ]
-            }
-            false => {
-                vec![]
+    let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+    let db = match get_model(&config, clock.clone()) {
+        Ok(db) => db,
+        Err(e) => return fail("opening the database", EXIT_DATABASE, &e),
+    };
+    if args.subcommand_matches("backtest").is_some() {
+        if let Err(e) = backtest(&db, &config) {
+            return fail("backtesting", EXIT_SELECT, &e);
+        }
+        return ExitCode::SUCCESS;
+    }
+    let http = get_http(&args);
+    let deadline = Instant::now() + get_time_budget(&config, 300);
+    let time = clock.now();
+    let workers = get_workers(&config, "workers", 4);
+    // each platform worker may start this many more, so keep it small
+    let detail_workers = get_workers(&config, "detail-workers", 2);
+    let platforms: Vec<Box<dyn PlatformAPI>> = match args.get_flag("get_some") {
+        true => {
+            let mut platforms: Vec<Box<dyn PlatformAPI>> = vec![
+                Metaculus::new_boxed(
+                    get_rate_limited(&config, &http, "metaculus", deadline),
+                    fetch_limit(&config, "metaculus", 100, deadline),
+                    get_access_token(&config, "metaculus"),
+                ),
+                Polymarket::new_boxed(
+                    get_rate_limited(&config, &http, "polymarket", deadline),
+                    fetch_limit(&config, "polymarket", 100, deadline),
+                ),
+                Manifold::new_boxed(
+                    get_rate_limited(&config, &http, "manifold", deadline),
+                    fetch_limit(&config, "manifold", 100, deadline),
+                    detail_workers,
+                ),
+                Kalshi::new_boxed(
+                    get_rate_limited(&config, &http, "kalshi", deadline),
+                    fetch_limit(&config, "kalshi", 100, deadline),
+                ),
+                PredictIt::new_boxed(
+                    get_rate_limited(&config, &http, "predictit", deadline),
+                    fetch_limit(&config, "predictit", 100, deadline),
+                ),
+            ];
+            // Cultivate Labs refuses requests without a token
+            for (name, platform) in [("gjopen", Platform::GJOpen), ("infer", Platform::Infer)] {
+                let Some(token) = config
+                    .as_ref()
+                    .and_then(|c| c.get_from(Some(name), "access-token"))
+                else {
+                    info!("no access-token in [{}], not fetching {}", name, platform);
+                    continue;
+                };
+                platforms.push(Cultivate::new_boxed(
+                    get_rate_limited(&config, &http, name, deadline),
+                    platform,
+                    fetch_limit(&config, name, 100, deadline),
+                    token.to_string(),
+                ));
             }
-        };
-        for p in platforms {
-            let ms = p.some_markets();
-            let time = chrono::Utc::now();
-            info!("fetched {} markets from {}", ms.len(), p.id());
-            for s in ms {
-                let p = s.platform.to_string();
-                if s.prob >= 0.0 && s.prob <= 1.0 {
-                    let t = s.title.clone();
-                    if let Some(_f64) =
-                        db.update_prob(time, p.as_str(), s.id, s.prob, s.url, s.title)
-                    {
-                    } else {
-                        debug!("No prev prob {} '{}' {:.1}%", p, t, s.prob * 100.0);
-                    }
-                } else {
-                    debug!("ignore {} '{}' {}", p, s.title, s.prob);
-                }
+            platforms
+        }
+        false => {
+            vec![]
+        }
+    };
+    let fetched = bounded_map(platforms, workers, |p| {
+        let ms = p.some_markets();
+        info!("fetched {} markets from {}", ms.len(), p.id());
+        (p.id(), ms)
+    });
+    // a platform with a failing insert is rolled back alone
+    let stored = db.transact(|| {
+        for (platform, ms) in fetched.iter() {
+            match db.transact(|| store_markets(&db, time, ms)) {
+                Ok(n) => info!("stored {} probabilities from {}", n, platform),
+                Err(e) => warn!("rolled back probabilities from {}: {}", platform, e),
             }
-            info!("stored probabilities from {}", p.id());
         }
+        Ok::<(), ModelError>(())
     });
+    if let Err(e) = stored {
+        return fail("storing probabilities", EXIT_STORE, &e);
+    }
     info!("fetching part done");
 
-    if args.get_flag("publish") {
-        db.transact(&|| {
-            let tooter = get_tooter(config.clone());
-            if let Some(q) = db.most_noteworthy_change() {
-                let msg = q.to_string();
-                info!("Most noteworthy change: {}", msg);
-                let since = db.duration_since_last_publication();
-                let wait = get_hours_silent(&config, 4);
-                if since.num_minutes() >= wait * 60 - 10 {
-                    tooter.expect("tooter").toot(msg);
-                    db.log_publication(q);
-                } else {
-                    info!(
-                        "Skip publication cause last one was only {} minutes ago not {} hours yet.",
-                        since.num_minutes(),
-                        wait
-                    )
-                }
-            } else {
-                info!("no noteworthy change");
-            }
-        });
-    } else {
+    if args.get_flag("compact") {
+        let retention = get_retention(&config);
+        let longest = get_windows(&config)
+            .iter()
+            .map(|w| w.minutes + w.early)
+            .max()
+            .unwrap_or(0);
+        if retention.full_days * 24 * 60 < longest {
+            warn!("full-days leaves nothing to compare the longest window with");
+        }
+        if let Err(e) = db.compact(&retention) {
+            return fail("compacting", EXIT_COMPACT, &e);
+        }
+    }
+
+    if !args.get_flag("publish") {
         info!("skip publication");
+        return ExitCode::SUCCESS;
+    }
+    // posts always go to the network unrecorded, recordings would keep the tokens
+    let direct: Arc<dyn HttpClient> = Arc::new(UreqClient::new());
+    let publishers = get_publishers(&direct, &config);
+    let change = match select(&db, &config) {
+        Ok(Some(change)) => change,
+        Ok(None) => {
+            if let Err(e) = follow_up(&db, &config, &publishers) {
+                return fail("updating the last post", EXIT_SELECT, &e);
+            }
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => return fail("selecting a change", EXIT_SELECT, &e),
+    };
+    if publishers.is_empty() {
+        return fail(
+            "publishing",
+            EXIT_PUBLISH,
+            &"no publication target configured",
+        );
+    }
+    // a failing target must not keep the others from getting the news
+    let mut delivered = 0;
+    for p in publishers.iter() {
+        let outcome = match p.publish(&change) {
+            Ok(receipt) => {
+                info!("published to {}", p.target());
+                delivered += 1;
+                Ok(receipt)
+            }
+            Err(e) => {
+                warn!("publishing to {} failed: {}", p.target(), e);
+                Err(e.to_string())
+            }
+        };
+        if let Err(e) = db.log_delivery(p.target(), &change, outcome) {
+            return fail("logging the publication", EXIT_DATABASE, &e);
+        }
+    }
+    if delivered == 0 {
+        return fail("publishing", EXIT_PUBLISH, &"no target accepted the change");
+    }
+    if let Err(e) = db.log_publication(change) {
+        return fail("logging the publication", EXIT_DATABASE, &e);
+    }
+    ExitCode::SUCCESS
+}
+
+/// The change to publish at the current time of the model clock, if any
+fn select(db: &Model, config: &Option<Ini>) -> Result<Option<Change>, ModelError> {
+    let ranking = get_ranking(config);
+    let Some(change) = db.most_noteworthy_change(ranking.as_ref(), &get_windows(config))? else {
+        info!("no noteworthy change");
+        return Ok(None);
+    };
+    info!("Most noteworthy change: {}", change);
+    let since = db.duration_since_last_publication()?;
+    let wait = get_hours_silent(config, 4);
+    if since.num_minutes() < wait * 60 - 10 {
+        info!(
+            "Skip publication cause last one was only {} minutes ago not {} hours yet.",
+            since.num_minutes(),
+            wait
+        );
+        return Ok(None);
     }
+    Ok(Some(change))
+}
+
+/// Within the silence window, targets which can edit update their latest post
+/// when its market moves again
+fn follow_up(
+    db: &Model,
+    config: &Option<Ini>,
+    publishers: &[Box<dyn Publisher>],
+) -> Result<(), ModelError> {
+    let editors: Vec<_> = publishers.iter().filter(|p| p.can_edit()).collect();
+    let wait = get_hours_silent(config, 4);
+    if editors.is_empty() || db.duration_since_last_publication()?.num_minutes() >= wait * 60 - 10 {
+        return Ok(());
+    }
+    let ranking = get_ranking(config);
+    let Some(change) = db.follow_up(ranking.as_ref(), &get_windows(config))? else {
+        return Ok(());
+    };
+    for p in editors {
+        let Some(receipt) = db.outdated_receipt(ranking.as_ref(), p.target(), &change)? else {
+            continue;
+        };
+        let outcome = match p.edit(&receipt, &change) {
+            Ok(true) => {
+                info!("updated the post on {}", p.target());
+                Ok(Some(receipt))
+            }
+            Ok(false) => continue, // nothing new to tell
+            Err(e) => {
+                warn!("updating the post on {} failed: {}", p.target(), e);
+                Err(e.to_string())
+            }
+        };
+        db.log_delivery(p.target(), &change, outcome)?;
+    }
+    Ok(())
+}
+
+/// Replays the stored history and prints each would-be publication.
+/// Compare strategies by running it with different config files.
+fn backtest(db: &Model, config: &Option<Ini>) -> Result<(), ModelError> {
+    let Some((first, last)) = db.history_range()? else {
+        info!("no history to replay");
+        return Ok(());
+    };
+    let published = db.backtest(first, last, chrono::Duration::hours(1), || {
+        select(db, config)
+    })?;
+    for (time, change) in published.iter() {
+        println!(
+            "{}\t{}",
+            time.format("%Y-%m-%d %H:%M"),
+            change.to_string().replace('\n', " ")
+        );
+    }
+    info!(
+        "{} publications from {} to {}",
+        published.len(),
+        first,
+        last
+    );
+    Ok(())
+}
+
+/// Returns how many probabilities were stored
+fn store_markets(
+    db: &Model,
+    time: chrono::DateTime<chrono::Utc>,
+    ms: &[MarketStatus],
+) -> Result<usize, ModelError> {
+    let mut stored = 0;
+    for s in ms {
+        let p = s.platform.to_string();
+        if s.prob >= 0.0 && s.prob <= 1.0 {
+            if db.update_prob(time, s)?.is_none() {
+                debug!("No prev prob {} '{}' {:.1}%", p, s.title, s.prob * 100.0);
+            }
+            stored += 1;
+        } else {
+            debug!("ignore {} '{}' {}", p, s.title, s.prob);
+        }
+    }
+    Ok(stored)
+}
+
+/// Exit codes telling which phase of a run failed
+const EXIT_DATABASE: u8 = 2;
+const EXIT_STORE: u8 = 3;
+const EXIT_SELECT: u8 = 4;
+const EXIT_PUBLISH: u8 = 5;
+const EXIT_COMPACT: u8 = 6;
+
+/// Returning from main lets the model close the database properly
+fn fail(phase: &str, code: u8, e: &dyn std::fmt::Display) -> ExitCode {
+    error!("{} failed: {}", phase, e);
+    ExitCode::from(code)
 }
 
 fn get_hours_silent(config: &Option<Ini>, default: i64) -> i64 {
@@ -117,25 +360,175 @@ fn get_hours_silent(config: &Option<Ini>, default: i64) -> i64 {
     }
 }
 
-fn get_model(config: &Option<Ini>) -> Model {
+fn get_workers(config: &Option<Ini>, key: &str, default: usize) -> usize {
+    if let Some(c) = config {
+        c.get_from(Some("general"), key)
+            .and_then(|w| w.parse::<usize>().ok())
+            .unwrap_or(default)
+    } else {
+        default
+    }
+}
+
+/// Sections like [window.day], hour, day and week if there are none
+fn get_windows(config: &Option<Ini>) -> Vec<DiffDuration> {
+    let mut windows = vec![];
+    for (name, section) in config.iter().flat_map(|c| c.iter()) {
+        let Some(name) = name.and_then(|n| n.strip_prefix("window.")) else {
+            continue;
+        };
+        // a missing late or early gets a default, an invalid one rejects the window
+        let minutes = |key: &str| section.get(key).map(parse_minutes);
+        let Some(length) = minutes("length").flatten().filter(|l| *l > 0) else {
+            warn!("window {} has no valid length", name);
+            continue;
+        };
+        let (Some(late), Some(early)) = (
+            minutes("late").unwrap_or(Some(length / 10)),
+            minutes("early").unwrap_or(Some(length / 10)),
+        ) else {
+            warn!("window {} has an invalid late or early, skipping it", name);
+            continue;
+        };
+        if late >= length {
+            warn!("window {} is not longer than its late, skipping it", name);
+            continue;
+        }
+        windows.push(DiffDuration {
+            label: section.get("label").unwrap_or(name).to_string(),
+            minutes: length,
+            late,
+            early,
+            weight: section
+                .get("weight")
+                .and_then(|w| w.parse().ok())
+                .unwrap_or(1.0),
+        });
+    }
+    if windows.is_empty() {
+        DiffDuration::defaults()
+    } else {
+        windows
+    }
+}
+
+/// Durations like "15m", "3h", "2d" or "1w" in minutes.
+/// The unit is required and negative durations are refused.
+fn parse_minutes(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let unit = s.chars().last()?;
+    let number = s[..s.len() - unit.len_utf8()].trim();
+    let factor = match unit {
+        'm' => 1,
+        'h' => 60,
+        'd' => 24 * 60,
+        'w' => 7 * 24 * 60,
+        _ => return None,
+    };
+    number
+        .parse::<i64>()
+        .ok()
+        .filter(|n| *n >= 0)
+        .and_then(|n| n.checked_mul(factor))
+}
+
+fn get_ranking(config: &Option<Ini>) -> Box<dyn RankingStrategy> {
+    let section = config.as_ref().and_then(|c| c.section(Some("ranking")));
+    let number = |name: &str| {
+        section
+            .and_then(|s| s.get(name))
+            .and_then(|v| v.parse::<f32>().ok())
+    };
+    let mut min_move = MinMove::default();
+    if let Some(v) = number("min-move") {
+        min_move.absolute = v;
+    }
+    for scoring in [Scoring::Volatility, Scoring::LogOdds] {
+        if let Some(v) = number(&format!("{}-min-move", scoring)) {
+            min_move.set(scoring, v);
+        }
+    }
+    match section.and_then(|s| s.get("strategy")).unwrap_or("scored") {
+        "blend" => Box::new(BlendedRanking {
+            weights: [Scoring::Absolute, Scoring::Volatility, Scoring::LogOdds]
+                .into_iter()
+                .map(|s| (s, number(&format!("{}-weight", s)).unwrap_or(0.0)))
+                .collect(),
+            min_move,
+        }),
+        other => {
+            if other != "scored" {
+                warn!("unknown ranking strategy {}, using scored", other);
+            }
+            Box::new(ScoredRanking {
+                scoring: get_scoring(config),
+                min_move,
+                skip_small: section
+                    .and_then(|s| s.get("skip-small"))
+                    .is_some_and(|v| v.trim() == "true"),
+            })
+        }
+    }
+}
+
+fn get_scoring(config: &Option<Ini>) -> Scoring {
+    let name = config
+        .as_ref()
+        .and_then(|c| c.get_from(Some("ranking"), "scoring"))
+        .unwrap_or("absolute");
+    name.parse().unwrap_or_else(|e| {
+        warn!("{}, using absolute", e);
+        Scoring::Absolute
+    })
+}
+
+fn get_retention(config: &Option<Ini>) -> Retention {
+    let mut r = Retention::default();
+    if let Some(section) = config.as_ref().and_then(|c| c.section(Some("retention"))) {
+        let days = |name: &str| section.get(name).and_then(|d| d.parse::<i64>().ok());
+        r.full_days = days("full-days").unwrap_or(r.full_days);
+        r.hourly_days = days("hourly-days").unwrap_or(r.hourly_days);
+    }
+    r
+}
+
+fn get_model(config: &Option<Ini>, clock: Arc<dyn Clock>) -> Result<Model, ModelError> {
     let s: &str = if let Some(c) = config {
         &c["general"]["database"]
     } else {
         ":memory:"
     };
 
-    Model::new(s)
+    Model::with_clock(s, clock)
 }
 
-fn get_access_token(config: &Option<Ini>) -> String {
+fn get_access_token(config: &Option<Ini>, name: &str) -> String {
     if let Some(c) = config.as_ref() {
-        if let Some(section) = c.section(Some("metaculus")) {
+        if let Some(section) = c.section(Some(name)) {
             return section["access-token"].to_string();
         }
     }
     "no access token".to_string()
 }
 
+fn get_time_budget(config: &Option<Ini>, default: u64) -> Duration {
+    let seconds = get_fetch_limit(config, "time-budget", default as i32);
+    match u64::try_from(seconds) {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            warn!("ignoring negative time-budget {}", seconds);
+            Duration::from_secs(default)
+        }
+    }
+}
+
+fn fetch_limit(config: &Option<Ini>, name: &str, default: i32, deadline: Instant) -> FetchLimit {
+    FetchLimit {
+        total: get_fetch_limit(config, name, default),
+        deadline,
+    }
+}
+
 fn get_fetch_limit(config: &Option<Ini>, name: &str, default: i32) -> i32 {
     if config.is_none() {
         return default;
@@ -150,11 +543,123 @@ fn get_fetch_limit(config: &Option<Ini>, name: &str, default: i32) -> i32 {
     limit.unwrap_or(default)
 }
 
-fn get_tooter(config: Option<Ini>) -> Option<Mastodon> {
-    let c = config?;
-    let m_section = c.section(Some("mastodon"))?;
-    let endpoint = m_section.get("api-endpoint")?;
-    let access_token = m_section.get("access-token")?;
-    let m_client = Mastodon::new(endpoint.to_string(), access_token.to_string());
-    Some(m_client)
+fn get_http(args: &clap::ArgMatches) -> Arc<dyn HttpClient> {
+    if let Some(dir) = args.get_one::<String>("replay") {
+        return Arc::new(Replay::new(PathBuf::from(dir)));
+    }
+    let client = Box::new(UreqClient::new());
+    if let Some(dir) = args.get_one::<String>("record") {
+        return Arc::new(Recorder::new(client, PathBuf::from(dir)));
+    }
+    Arc::from(client as Box<dyn HttpClient>)
+}
+
+/// Platform specific retries and request rate
+fn get_rate_limited(
+    config: &Option<Ini>,
+    http: &Arc<dyn HttpClient>,
+    name: &str,
+    deadline: Instant,
+) -> Arc<dyn HttpClient> {
+    let section = config.as_ref().and_then(|c| c.section(Some("rate-limits")));
+    let get = |key: &str| section.and_then(|s| s.get(key)?.parse::<f64>().ok());
+    let per_second = get(name).unwrap_or(10.0);
+    let backoff = match get("backoff-seconds") {
+        Some(b) if b.is_finite() && b >= 0.0 => b,
+        Some(b) => {
+            warn!("ignoring invalid backoff-seconds {}", b);
+            1.0
+        }
+        None => 1.0,
+    };
+    let policy = RetryPolicy {
+        retries: get("retries").unwrap_or(4.0) as u32,
+        backoff: Duration::from_secs_f64(backoff),
+        max_wait: Duration::from_secs(60),
+        min_interval: Duration::from_secs_f64(1.0 / per_second.max(0.01)),
+        deadline: Some(deadline),
+    };
+    Arc::new(Retrying::new(http.clone(), policy))
+}
+
+/// One publisher per target section like [bluesky] or [mastodon.second-account]
+fn get_publishers(http: &Arc<dyn HttpClient>, config: &Option<Ini>) -> Vec<Box<dyn Publisher>> {
+    let mut publishers = vec![];
+    for (name, section) in config.iter().flat_map(|c| c.iter()) {
+        let Some(name) = name else {
+            continue;
+        };
+        let publisher = match name.split('.').next().unwrap_or(name) {
+            "mastodon" => get_mastodon(http, name, section),
+            "bluesky" => get_bluesky(http, name, section),
+            "telegram" => get_telegram(http, name, section),
+            _ => continue,
+        };
+        match publisher {
+            Some(p) => publishers.push(p),
+            None => warn!("[{}] is incomplete, not publishing there", name),
+        }
+    }
+    publishers
+}
+
+fn max_length(section: &ini::Properties, default: usize) -> usize {
+    section
+        .get("max-length")
+        .and_then(|m| m.parse().ok())
+        .unwrap_or(default)
+}
+
+fn get_mastodon(
+    http: &Arc<dyn HttpClient>,
+    name: &str,
+    section: &ini::Properties,
+) -> Option<Box<dyn Publisher>> {
+    Some(Box::new(Mastodon::new(
+        http.clone(),
+        name.to_string(),
+        section.get("api-endpoint")?.to_string(),
+        section.get("access-token")?.to_string(),
+        max_length(section, 500),
+    )))
+}
+
+fn get_bluesky(
+    http: &Arc<dyn HttpClient>,
+    name: &str,
+    section: &ini::Properties,
+) -> Option<Box<dyn Publisher>> {
+    Some(Box::new(Bluesky::new(
+        http.clone(),
+        name.to_string(),
+        section
+            .get("service")
+            .unwrap_or("https://bsky.social")
+            .to_string(),
+        section.get("handle")?.to_string(),
+        section.get("app-password")?.to_string(),
+        max_length(section, 300),
+    )))
+}
+
+fn get_telegram(
+    http: &Arc<dyn HttpClient>,
+    name: &str,
+    section: &ini::Properties,
+) -> Option<Box<dyn Publisher>> {
+    Some(Box::new(Telegram::new(
+        http.clone(),
+        name.to_string(),
+        section
+            .get("api-endpoint")
+            .unwrap_or("https://api.telegram.org")
+            .to_string(),
+        section.get("bot-token")?.to_string(),
+        section.get("chat-id")?.to_string(),
+        max_length(section, 4096),
+        section
+            .get("edit")
+            .map(|e| e.trim() == "true")
+            .unwrap_or(false),
+    )))
 }
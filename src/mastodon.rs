@@ -1,40 +1,69 @@
+use crate::http::{HttpClient, HttpError, Request};
+use crate::model::Change;
+use crate::publisher::{fit, Publisher};
 use log::*;
-use ureq::*;
+use std::sync::Arc;
 
 pub struct Mastodon {
+    http: Arc<dyn HttpClient>,
+    name: String,
     endpoint: String,
     access_token: String,
+    /// Longest status the instance accepts, in characters
+    max_length: usize,
 }
 
 impl Mastodon {
-    pub fn new(endpoint: String, access_token: String) -> Self {
+    pub fn new(
+        http: Arc<dyn HttpClient>,
+        name: String,
+        endpoint: String,
+        access_token: String,
+        max_length: usize,
+    ) -> Self {
         Mastodon {
+            http,
+            name,
             endpoint,
             access_token,
+            max_length,
         }
     }
 
-    pub fn toot(&self, text: String) {
+    pub fn toot(&self, text: String) -> Result<(), HttpError> {
         let statuses = self.endpoint.clone() + "statuses/";
-        let call = ureq::post(statuses.as_str())
-            .set("Accept", "application/json")
-            .set(
+        let request = Request::post(statuses.as_str())
+            .header("Accept", "application/json")
+            .header(
                 "Authorization",
                 format!("Bearer {}", self.access_token).as_str(),
             )
-            .send_form(&[
+            .form(&[
                 ("status", text.as_str()),
                 ("visibility", "public"),
                 ("language", "en"),
             ]);
-        match call {
-            Ok(_response) => {}
-            Err(Error::Status(code, response)) => {
-                debug!("error status {}: {:?}", code, response);
+        match self.http.send(&request) {
+            Ok(_response) => Ok(()),
+            Err(HttpError::Status(response)) => {
+                debug!("error status {}: {:?}", response.status, response);
+                Err(HttpError::Status(response))
             }
-            Err(_) => {
+            Err(e) => {
                 error!("some kind of io/transport error");
+                Err(e)
             }
-        };
+        }
+    }
+}
+
+impl Publisher for Mastodon {
+    fn target(&self) -> &str {
+        &self.name
+    }
+
+    fn publish(&self, change: &Change) -> Result<Option<String>, HttpError> {
+        self.toot(fit(&change.to_string(), self.max_length))?;
+        Ok(None)
     }
 }
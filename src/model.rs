@@ -1,26 +1,128 @@
+#[cfg(test)]
+use crate::clock::SystemClock;
+use crate::clock::{Clock, ManualClock};
+use crate::platforms::{MarketStatus, Metadata, Scale, ScaleKind};
+use crate::ranking::RankingStrategy;
 use chrono::prelude::*;
 use log::*;
 use sqlite::Connection;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
 
 pub struct Model {
     c: Connection,
+    /// Number of open transactions, inner ones are savepoints
+    depth: Cell<u32>,
+    /// Everything time-dependent asks this instead of the system time
+    clock: RefCell<Arc<dyn Clock>>,
+}
+
+#[derive(Debug)]
+pub enum ModelError {
+    /// The database refused, e.g. because it is locked or corrupt
+    Sqlite(sqlite::Error),
+    /// The schema cannot be used or migrated
+    Schema(String),
+    /// A stored value does not make sense
+    Corrupt(String),
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelError::Sqlite(e) => write!(f, "sqlite: {}", e),
+            ModelError::Schema(msg) => write!(f, "schema: {}", msg),
+            ModelError::Corrupt(msg) => write!(f, "corrupt data: {}", msg),
+        }
+    }
+}
+
+impl From<sqlite::Error> for ModelError {
+    fn from(e: sqlite::Error) -> Self {
+        ModelError::Sqlite(e)
+    }
 }
 
 impl Model {
-    pub fn new(path: &str) -> Self {
+    #[cfg(test)]
+    pub fn new(path: &str) -> Result<Self, ModelError> {
+        Model::with_clock(path, Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(path: &str, clock: Arc<dyn Clock>) -> Result<Self, ModelError> {
         let db = Model {
-            c: sqlite::open(path).unwrap(),
+            c: sqlite::open(path)?,
+            depth: Cell::new(0),
+            clock: RefCell::new(clock),
         };
-        init_tables(&db.c);
-        db
+        migrate(&db.c)?;
+        Ok(db)
+    }
+
+    /// Run f inside a transaction and return its result.
+    /// Nested calls become savepoints, so an inner failure only undoes the inner part.
+    /// Everything since the start is rolled back if f returns an error or panics.
+    pub fn transact<T, E: From<ModelError>>(
+        &self,
+        f: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.savepoint(f, true)
     }
 
-    pub fn transact(&self, f: &dyn Fn()) {
-        debug!("transation begin");
-        self.c.execute("BEGIN TRANSACTION;").expect("begin");
-        f();
-        self.c.execute("COMMIT;").expect("commit");
-        debug!("transaction commit");
+    /// Like transact, but always rolls back
+    fn dry_run<T, E: From<ModelError>>(&self, f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+        self.savepoint(f, false)
+    }
+
+    fn savepoint<T, E: From<ModelError>>(
+        &self,
+        f: impl FnOnce() -> Result<T, E>,
+        keep: bool,
+    ) -> Result<T, E> {
+        let depth = self.depth.get();
+        let name = format!("sp{}", depth);
+        debug!("transaction {} begin", name);
+        self.c
+            .execute(format!("SAVEPOINT {};", name))
+            .map_err(ModelError::from)?;
+        self.depth.set(depth + 1);
+        let result = panic::catch_unwind(AssertUnwindSafe(f));
+        self.depth.set(depth);
+        match result {
+            Ok(Ok(value)) if !keep => {
+                self.rollback(&name);
+                Ok(value)
+            }
+            Ok(Ok(value)) => {
+                self.c
+                    .execute(format!("RELEASE {};", name))
+                    .map_err(ModelError::from)?;
+                debug!("transaction {} commit", name);
+                Ok(value)
+            }
+            Ok(Err(e)) => {
+                self.rollback(&name);
+                Err(e)
+            }
+            Err(cause) => {
+                self.rollback(&name);
+                panic::resume_unwind(cause)
+            }
+        }
+    }
+
+    fn now(&self) -> DateTime<Utc> {
+        self.clock.borrow().now()
+    }
+
+    fn rollback(&self, name: &str) {
+        debug!("transaction {} rollback", name);
+        let query = format!("ROLLBACK TO {}; RELEASE {};", name, name);
+        if let Err(e) = self.c.execute(query) {
+            warn!("rollback of {} failed: {}", name, e);
+        }
     }
 
     /// Archive new probability info
@@ -28,107 +130,471 @@ impl Model {
     pub fn update_prob(
         &self,
         time: DateTime<Utc>,
-        platform: &str,
-        id: String,
-        prob: f32,
-        url: String,
-        title: String,
-    ) -> Option<f32> {
-        let prev_prob = previous_probability(&self.c, &platform, &id);
-        match insert_probability(&self.c, prob, platform, &id, &title, &url, &time) {
-            Ok(_) => {}
-            Err(e) => {
-                warn!("failed to insert prob: {}", e)
-                // we still continue...
-            }
+        s: &MarketStatus,
+    ) -> Result<Option<f32>, ModelError> {
+        let platform = s.platform.to_string();
+        let prev_prob = previous_probability(&self.c, &platform, &s.id)?;
+        insert_probability(&self.c, &platform, s, &time)?;
+        if let Some(scale) = &s.scale {
+            insert_scale(&self.c, &platform, &s.id, scale)?;
+        }
+        Ok(prev_prob)
+    }
+
+    /// Changes of all markets which were not published already
+    /// Only what was known at the current time of the clock is considered
+    pub fn candidates(&self, windows: &[DiffDuration]) -> Result<Vec<Change>, ModelError> {
+        let previous = last_publications(&self.c, self.now())?;
+        let changes = self.changes(windows)?;
+        Ok(changes
+            .into_iter()
+            .filter(|c| !is_published(c, &previous))
+            .collect())
+    }
+
+    /// The most noteworthy change of the market published last, to update that post
+    pub fn follow_up(
+        &self,
+        strategy: &dyn RankingStrategy,
+        windows: &[DiffDuration],
+    ) -> Result<Option<Change>, ModelError> {
+        let previous = last_publications(&self.c, self.now())?;
+        let Some(last) = previous.first() else {
+            return Ok(Option::None);
         };
-        prev_prob
+        let same: Vec<Change> = self
+            .changes(windows)?
+            .into_iter()
+            .filter(|c| &group_key(&c.platform, &c.id) == last)
+            .collect();
+        Ok(strategy.rank(same).into_iter().next())
     }
 
-    pub fn most_noteworthy_change(&self) -> Option<Change> {
-        let mut most_noteworthy = Change::new_from05(DiffDuration::Week, 0.5);
-        let previous = last_publications(&self.c);
-        let ago = duration_since_last_update(&self.c).unwrap_or(chrono::Duration::minutes(1));
+    /// Changes of all markets for each window
+    fn changes(&self, windows: &[DiffDuration]) -> Result<Vec<Change>, ModelError> {
+        let now = self.now();
+        let mut ret = vec![];
+        let ago = duration_since_last_update(&self.c, now)?.unwrap_or(chrono::Duration::minutes(1));
         info!("looking {} minutes ago", ago.num_minutes());
-        let timestamps = query_timestamps(&self.c, ago);
+        let timestamps = query_timestamps(&self.c, ago, windows, now)?;
         info!("found {} candidates for news", timestamps.len());
         for ts in timestamps {
             let plat = &ts.platform;
-            let p_now = get_prob_by_time(&self.c, plat, &ts.id, &ts.latest).expect("latest prob");
-            let c_hour = ts.as_change(&self.c, p_now, ts.hour.clone(), DiffDuration::Hour);
-            set_if_not_published(&mut most_noteworthy, c_hour, &previous);
-            let c_day = ts.as_change(&self.c, p_now, ts.day.clone(), DiffDuration::Day);
-            set_if_not_published(&mut most_noteworthy, c_day, &previous);
-            let c_week = ts.as_change(&self.c, p_now, ts.week.clone(), DiffDuration::Week);
-            set_if_not_published(&mut most_noteworthy, c_week, &previous);
-        }
-        debug!(
-            "note before {} and after {}",
-            most_noteworthy.p_before, most_noteworthy.p_after
-        );
-        if most_noteworthy.url == "url" {
-            info!("found nothing to even consider noteworthyness");
-            Option::None
-        } else if (most_noteworthy.p_after - most_noteworthy.p_before).abs() < 0.2 {
-            info!("not even one 20% move");
-            Option::None
-        } else {
-            Option::Some(most_noteworthy)
-        }
-    }
-    pub fn log_publication(&self, c: Change) {
-        let q = "INSERT INTO log (type, content) VALUES ('pub', ?);";
-        // multiple-choice markets get a postfix for each answer
-        // ignore the postfix for logging
-        let id = c.id.split_ascii_whitespace().next().expect("some id");
-        let mut s = self.c.prepare(q).expect("prep check");
-        s.bind((1, format!("{} {}", c.platform, id).as_str()))
-            .expect("bind");
-        s.next().expect("execute");
+            let Some(p_now) = get_prob_by_time(&self.c, plat, &ts.id, &ts.latest)? else {
+                warn!("no latest probability for {} {}", plat, ts.id);
+                continue;
+            };
+            for (t, duration) in ts.earlier.iter().zip(windows) {
+                if let Some(c) = ts.as_change(&self.c, p_now, t.clone(), duration.clone(), now)? {
+                    ret.push(c);
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn most_noteworthy_change(
+        &self,
+        strategy: &dyn RankingStrategy,
+        windows: &[DiffDuration],
+    ) -> Result<Option<Change>, ModelError> {
+        let ranked = strategy.rank(self.candidates(windows)?);
+        match ranked.into_iter().next() {
+            Some(c) => {
+                debug!("note before {} and after {}", c.p_before, c.p_after);
+                Ok(Some(c))
+            }
+            None => {
+                info!("nothing noteworthy enough");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Downsample old probabilities according to the retention policy.
+    /// Returns how many raw and hourly rows were folded into aggregates.
+    pub fn compact(&self, r: &Retention) -> Result<(usize, usize), ModelError> {
+        let now = self.now();
+        self.transact(|| {
+            let raw_cutoff = (now - chrono::Duration::days(r.full_days))
+                .format("%Y-%m-%d %H:00:00")
+                .to_string();
+            let hourly_cutoff = (now - chrono::Duration::days(r.full_days + r.hourly_days))
+                .format("%Y-%m-%d 00:00:00")
+                .to_string();
+            let raw = downsample(&self.c, &RAW, &HOURLY, &raw_cutoff)?;
+            let hourly = downsample(&self.c, &HOURLY, &DAILY, &hourly_cutoff)?;
+            info!("compacted {} raw and {} hourly rows", raw, hourly);
+            Ok((raw, hourly))
+        })
+    }
+
+    pub fn log_publication(&self, c: Change) -> Result<(), ModelError> {
+        let now = self.now();
+        let q = "INSERT INTO log (time, type, content) VALUES (?, 'pub', ?);";
+        let mut s = self.c.prepare(q)?;
+        s.bind((1, db_time(&now).as_str()))?;
+        s.bind((2, group_key(&c.platform, &c.id).as_str()))?;
+        s.next()?;
         info!("log pub {} {}", c.platform, c.id);
+        Ok(())
+    }
+    /// Record whether a change reached one of the publication targets.
+    /// A delivery may come with a receipt, the id of the post on the target,
+    /// which is kept together with the probability the post told.
+    pub fn log_delivery(
+        &self,
+        target: &str,
+        c: &Change,
+        outcome: Result<Option<String>, String>,
+    ) -> Result<(), ModelError> {
+        let q = "INSERT INTO log (time, type, content, receipt, prob) VALUES (?, ?, ?, ?, ?);";
+        let mut s = self.c.prepare(q)?;
+        let content = format!("{} {}", target, group_key(&c.platform, &c.id));
+        s.bind((1, db_time(&self.now()).as_str()))?;
+        match outcome {
+            Ok(receipt) => {
+                s.bind((2, "target-ok"))?;
+                s.bind((3, content.as_str()))?;
+                s.bind((4, receipt.as_deref()))?;
+                s.bind((5, c.p_after as f64))?;
+            }
+            Err(e) => {
+                s.bind((2, "target-failed"))?;
+                s.bind((3, format!("{}: {}", content, e).as_str()))?;
+                s.bind((4, Option::<&str>::None))?;
+                s.bind((5, Option::<f64>::None))?;
+            }
+        }
+        s.next()?;
+        Ok(())
     }
-    pub fn duration_since_last_publication(&self) -> chrono::Duration {
-        let query = "SELECT time FROM log ORDER BY time DESC LIMIT 1;";
-        let mut s = self.c.prepare(query).expect("prepare");
-        if let Ok(sqlite::State::Row) = s.next() {
-            let t = s.read::<String, _>("time").expect("time");
-            let naive = NaiveDateTime::parse_from_str(t.as_str(), "%Y-%m-%d %H:%M:%S");
-            Utc::now() - naive.expect("parsed").and_utc()
+
+    /// Receipt of the post about this market on the target,
+    /// if the market moved enough for the strategy since the post told it
+    pub fn outdated_receipt(
+        &self,
+        strategy: &dyn RankingStrategy,
+        target: &str,
+        c: &Change,
+    ) -> Result<Option<String>, ModelError> {
+        let Some(receipt) = self.last_receipt(target, c)? else {
+            return Ok(Option::None);
+        };
+        let moved = match receipt.prob {
+            Some(p_before) => !strategy
+                .rank(vec![Change {
+                    p_before,
+                    ..c.clone()
+                }])
+                .is_empty(),
+            None => true, // delivered before probabilities were kept
+        };
+        Ok(Some(receipt.id).filter(|_| moved))
+    }
+
+    /// Receipt of the latest delivery of this market to the target
+    pub fn last_receipt(&self, target: &str, c: &Change) -> Result<Option<Receipt>, ModelError> {
+        let q = "SELECT receipt, prob FROM log WHERE type = 'target-ok' AND content = ?
+            AND time <= ? AND receipt IS NOT NULL ORDER BY time DESC, rowid DESC LIMIT 1;";
+        let mut s = self.c.prepare(q)?;
+        let content = format!("{} {}", target, group_key(&c.platform, &c.id));
+        s.bind((1, content.as_str()))?;
+        s.bind((2, db_time(&self.now()).as_str()))?;
+        if let sqlite::State::Row = s.next()? {
+            Ok(Option::Some(Receipt {
+                id: s.read::<String, _>("receipt")?,
+                prob: s.read::<Option<f64>, _>("prob")?.map(|p| p as f32),
+            }))
         } else {
-            chrono::Duration::zero()
+            Ok(Option::None)
         }
     }
+
+    /// Failed deliveries do not count, so they are tried again next time
+    pub fn duration_since_last_publication(&self) -> Result<chrono::Duration, ModelError> {
+        let now = self.now();
+        let query = "SELECT time FROM log WHERE type IN ('pub', 'creation') ORDER BY time DESC;";
+        let mut s = self.c.prepare(query)?;
+        while let sqlite::State::Row = s.next()? {
+            let t = parse_db_time(&s.read::<String, _>("time")?)?;
+            // during a backtest later publications have not happened yet
+            if t <= now {
+                return Ok(now - t);
+            }
+        }
+        Ok(chrono::Duration::MAX) // never
+    }
+
+    /// Times of the first and the last raw probability.
+    /// Compacted history is left out, changes are only found among raw probabilities.
+    pub fn history_range(&self) -> Result<Option<TimeRange>, ModelError> {
+        let query = "SELECT MIN(time) AS first, MAX(time) AS last FROM probabilities;";
+        let mut s = self.c.prepare(query)?;
+        s.next()?;
+        let range = match (
+            s.read::<Option<String>, _>("first")?,
+            s.read::<Option<String>, _>("last")?,
+        ) {
+            (Some(first), Some(last)) => (parse_db_time(&first)?, parse_db_time(&last)?),
+            _ => return Ok(None),
+        };
+        let query = "SELECT MIN(bucket) AS first FROM (
+            SELECT bucket FROM probabilities_hourly UNION ALL SELECT bucket FROM probabilities_daily);";
+        let mut s = self.c.prepare(query)?;
+        s.next()?;
+        if let Some(compacted) = s.read::<Option<String>, _>("first")? {
+            warn!(
+                "history since {} is compacted, only the raw part since {} can be replayed",
+                compacted, range.0
+            );
+        }
+        Ok(Some(range))
+    }
+
+    /// Replay the history from one time to another,
+    /// select decides what would have been published at each step.
+    /// The clock is stopped meanwhile, earlier publications are ignored and nothing is stored.
+    pub fn backtest(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        step: chrono::Duration,
+        mut select: impl FnMut() -> Result<Option<Change>, ModelError>,
+    ) -> Result<Vec<(DateTime<Utc>, Change)>, ModelError> {
+        let replay = Arc::new(ManualClock::new(from));
+        let _restore = ClockGuard {
+            clock: &self.clock,
+            previous: Some(self.clock.replace(replay.clone())),
+        };
+        self.dry_run(|| {
+            self.c.execute("DELETE FROM log WHERE type = 'pub';")?;
+            let mut published = vec![];
+            while replay.now() <= to {
+                if let Some(change) = select()? {
+                    self.log_publication(change.clone())?;
+                    published.push((replay.now(), change));
+                }
+                replay.advance(step);
+            }
+            Ok(published)
+        })
+    }
 }
 
-impl Drop for Model {
+/// Puts the previous clock back when dropped, even while a panic unwinds
+struct ClockGuard<'a> {
+    clock: &'a RefCell<Arc<dyn Clock>>,
+    previous: Option<Arc<dyn Clock>>,
+}
+
+impl Drop for ClockGuard<'_> {
     fn drop(&mut self) {
-        // we only care about probablities from a week ago
-        let query = "DELETE FROM probabilities WHERE time < datetime('now', '-10 days');";
-        self.c.execute(query).expect("Delete old probabilities");
+        if let Some(previous) = self.previous.take() {
+            self.clock.replace(previous);
+        }
+    }
+}
 
+impl Drop for Model {
+    fn drop(&mut self) {
         // sqlite suggests to run this "once, just prior to closing each database connection"
         // https://www.sqlite.org/lang_analyze.html
         self.c.execute("PRAGMA optimize;").ok();
     }
 }
 
-fn duration_since_last_update(c: &Connection) -> Option<chrono::Duration> {
-    let query = "SELECT time FROM probabilities ORDER BY time DESC LIMIT 1;";
-    let mut s = c.prepare(query).ok()?;
-    if let Ok(sqlite::State::Row) = s.next() {
-        let t = s.read::<String, _>("time").ok()?;
-        let naive = NaiveDateTime::parse_from_str(t.as_str(), "%Y-%m-%d %H:%M:%S");
-        Some(Utc::now() - naive.expect("parsed").and_utc())
+/// How long probabilities are kept at which resolution.
+/// Daily aggregates are kept forever.
+#[derive(Debug, Clone)]
+pub struct Retention {
+    /// Every observation for this many days
+    pub full_days: i64,
+    /// Then hourly open, close, min and max for this many days
+    pub hourly_days: i64,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Retention {
+            full_days: 10,
+            hourly_days: 90,
+        }
+    }
+}
+
+/// Where probabilities of one resolution are stored
+struct Level {
+    table: &'static str,
+    time: &'static str,
+    /// strftime format of the bucket start
+    bucket: &'static str,
+    open: &'static str,
+    close: &'static str,
+    min: &'static str,
+    max: &'static str,
+}
+
+const RAW: Level = Level {
+    table: "probabilities",
+    time: "time",
+    bucket: "%Y-%m-%d %H:%M:%S",
+    open: "prob",
+    close: "prob",
+    min: "prob",
+    max: "prob",
+};
+
+const HOURLY: Level = Level {
+    table: "probabilities_hourly",
+    time: "bucket",
+    bucket: "%Y-%m-%d %H:00:00",
+    open: "open",
+    close: "close",
+    min: "min",
+    max: "max",
+};
+
+const DAILY: Level = Level {
+    table: "probabilities_daily",
+    time: "bucket",
+    bucket: "%Y-%m-%d 00:00:00",
+    open: "open",
+    close: "close",
+    min: "min",
+    max: "max",
+};
+
+/// Fold rows before cutoff into the buckets of a coarser level.
+/// Aggregates only keep the probability: the lower/upper interval and
+/// volume/liquidity of raw rows are dropped with them.
+fn downsample(
+    c: &Connection,
+    from: &Level,
+    into: &Level,
+    cutoff: &str,
+) -> Result<usize, ModelError> {
+    let Level {
+        table: from_table,
+        time,
+        open,
+        close,
+        min,
+        max,
+        ..
+    } = from;
+    let (into_table, bucket) = (into.table, into.bucket);
+    // buckets which were compacted before only get extended
+    let query = format!(
+        "INSERT INTO {into_table} (platform, id, bucket, open, close, min, max)
+        SELECT platform, id, bucket, first_open, last_close, MIN(low), MAX(high) FROM (
+            SELECT platform, id, strftime('{bucket}', {time}) AS bucket, {min} AS low, {max} AS high,
+            FIRST_VALUE({open}) OVER w AS first_open,
+            LAST_VALUE({close}) OVER w AS last_close
+            FROM {from_table} WHERE {time} < ?
+            WINDOW w AS (PARTITION BY platform, id, strftime('{bucket}', {time}) ORDER BY {time}
+                ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING))
+        GROUP BY platform, id, bucket
+        ON CONFLICT (platform, id, bucket) DO UPDATE SET close=excluded.close,
+            min=MIN(min, excluded.min), max=MAX(max, excluded.max);"
+    );
+    let mut s = c.prepare(query)?;
+    s.bind((1, cutoff))?;
+    s.next()?;
+    let mut s = c.prepare(format!("DELETE FROM {} WHERE {} < ?;", from_table, time))?;
+    s.bind((1, cutoff))?;
+    s.next()?;
+    Ok(c.change_count())
+}
+
+/// A post on a publication target
+#[derive(Debug, PartialEq)]
+pub struct Receipt {
+    /// Id of the post on the target
+    pub id: String,
+    /// Probability the post told, unknown for posts from before it was kept
+    pub prob: Option<f32>,
+}
+
+/// First and last time
+pub type TimeRange = (DateTime<Utc>, DateTime<Utc>);
+
+/// Times are stored like "2025-11-01 09:00:00" in UTC
+fn db_time(t: &DateTime<Utc>) -> String {
+    t.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn parse_db_time(t: &str) -> Result<DateTime<Utc>, ModelError> {
+    match NaiveDateTime::parse_from_str(t, "%Y-%m-%d %H:%M:%S") {
+        Ok(naive) => Ok(naive.and_utc()),
+        Err(e) => Err(ModelError::Corrupt(format!("time '{}': {}", t, e))),
+    }
+}
+
+fn duration_since_last_update(
+    c: &Connection,
+    now: DateTime<Utc>,
+) -> Result<Option<chrono::Duration>, ModelError> {
+    let query = "SELECT time FROM probabilities WHERE time <= ? ORDER BY time DESC LIMIT 1;";
+    let mut s = c.prepare(query)?;
+    s.bind((1, db_time(&now).as_str()))?;
+    if let sqlite::State::Row = s.next()? {
+        let t = s.read::<String, _>("time")?;
+        Ok(Some(now - parse_db_time(&t)?))
     } else {
-        Option::None
+        Ok(Option::None)
     }
 }
 
+/// Time span over which a change is measured.
+/// The earlier observation may be up to `late` minutes younger
+/// or `early` minutes older than `minutes` before the latest one.
 #[derive(PartialEq, Debug, Clone)]
-pub enum DiffDuration {
-    Hour,
-    Day,
-    Week,
+pub struct DiffDuration {
+    /// How the post tells it, like "a day"
+    pub label: String,
+    pub minutes: i64,
+    pub late: i64,
+    pub early: i64,
+    /// Shorter windows get more weight, a move within an hour is bigger news
+    pub weight: f32,
+}
+
+impl DiffDuration {
+    pub fn hour() -> Self {
+        DiffDuration {
+            label: "an hour".to_string(),
+            minutes: 60,
+            late: 5,
+            early: 39,
+            weight: 2.0 * 3.0,
+        }
+    }
+
+    pub fn day() -> Self {
+        DiffDuration {
+            label: "a day".to_string(),
+            minutes: 24 * 60,
+            late: 2 * 60,
+            early: 4 * 60,
+            weight: 2.0,
+        }
+    }
+
+    pub fn week() -> Self {
+        DiffDuration {
+            label: "a week".to_string(),
+            minutes: 7 * 24 * 60,
+            late: 24 * 60,
+            early: 24 * 60,
+            weight: 1.0,
+        }
+    }
+
+    /// Hour, day and week
+    pub fn defaults() -> Vec<Self> {
+        vec![Self::hour(), Self::day(), Self::week()]
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
@@ -140,126 +606,1038 @@ pub struct Change {
     p_after: f32,
     url: String,
     title: String,
+    scale: Option<Scale>,
+    /// Interquartile range after the change
+    interval: Option<(f32, f32)>,
+    meta: Metadata,
+    /// Typical size of a move over this duration, from the history before the change
+    sigma: Option<f32>,
+    /// How this change was picked, which decides how it is told
+    scoring: Scoring,
+    /// When the change was observed
+    time: DateTime<Utc>,
+}
+
+/// How the size of a change is judged
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scoring {
+    /// Probability delta weighted by the duration
+    Absolute,
+    /// Probability delta relative to how much the market usually moves, like a z-score
+    Volatility,
+    /// Shift in log-odds weighted by the duration, so moves near 0% or 100% count more
+    LogOdds,
+}
+
+impl fmt::Display for Scoring {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Scoring::Absolute => write!(f, "absolute"),
+            Scoring::Volatility => write!(f, "volatility"),
+            Scoring::LogOdds => write!(f, "log-odds"),
+        }
+    }
+}
+
+impl std::str::FromStr for Scoring {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "absolute" => Ok(Scoring::Absolute),
+            "volatility" => Ok(Scoring::Volatility),
+            "log-odds" => Ok(Scoring::LogOdds),
+            _ => Err(format!("unknown scoring {}", s)),
+        }
+    }
 }
 
+/// Flat markets would make every move infinitely surprising
+const MIN_SIGMA: f32 = 0.02;
+
 impl PartialOrd for Change {
     fn partial_cmp(&self, other: &Change) -> Option<std::cmp::Ordering> {
-        let diff_left = (self.p_after - self.p_before).abs() * diff_factor(&self.duration);
-        let diff_right = (other.p_after - other.p_before).abs() * diff_factor(&other.duration);
-        diff_left.partial_cmp(&diff_right)
+        self.score(Scoring::Absolute)
+            .partial_cmp(&other.score(Scoring::Absolute))
     }
 }
 
 impl Change {
-    fn new_from05(duration: DiffDuration, p_after: f32) -> Self {
+    #[cfg(test)]
+    pub fn new_from05(duration: DiffDuration, p_after: f32) -> Self {
+        Change::new_from(duration, 0.5, p_after)
+    }
+
+    #[cfg(test)]
+    pub fn new_from(duration: DiffDuration, p_before: f32, p_after: f32) -> Self {
         Change {
             platform: "platform".to_string(),
             id: "id".to_string(),
             duration,
-            p_before: 0.5,
+            p_before,
             p_after,
             url: "url".to_string(),
             title: "title".to_string(),
+            scale: None,
+            interval: None,
+            meta: Metadata::default(),
+            sigma: None,
+            scoring: Scoring::Absolute,
+            time: Utc::now(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// When the change was observed
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    /// Probability move, positive for up
+    pub fn delta(&self) -> f32 {
+        self.p_after - self.p_before
+    }
+
+    /// The scoring which really judges this change.
+    /// Numeric and date markets store a position within their range, which has no odds.
+    pub fn judged_by(&self, scoring: Scoring) -> Scoring {
+        match scoring {
+            Scoring::LogOdds if self.scale.is_some() => Scoring::Absolute,
+            _ => scoring,
         }
     }
 
-    pub fn to_string(&self) -> String {
+    /// Tell the change the way this scoring sees it
+    pub fn told_by(self, scoring: Scoring) -> Self {
+        let scoring = self.judged_by(scoring);
+        Change { scoring, ..self }
+    }
+
+    /// Size of the move in the units of the scoring, not weighted by the duration
+    pub fn moved(&self, scoring: Scoring) -> f32 {
+        match self.judged_by(scoring) {
+            Scoring::Absolute => self.delta().abs(),
+            Scoring::Volatility => self.score(scoring),
+            Scoring::LogOdds => (log_odds(self.p_after) - log_odds(self.p_before)).abs(),
+        }
+    }
+
+    /// Higher is more noteworthy
+    pub fn score(&self, scoring: Scoring) -> f32 {
+        let delta = (self.p_after - self.p_before).abs();
+        match self.judged_by(scoring) {
+            Scoring::Absolute => delta * self.duration.weight,
+            // without enough history a new market would look perfectly flat
+            Scoring::Volatility => self.sigma.map_or(0.0, |s| delta / s.max(MIN_SIGMA)),
+            Scoring::LogOdds => {
+                (log_odds(self.p_after) - log_odds(self.p_before)).abs() * self.duration.weight
+            }
+        }
+    }
+
+    /// Like "on $2.0M volume, closes in 3 days" or empty
+    fn context(&self) -> String {
+        let mut parts = vec![];
+        if let Some(volume) = self.meta.volume {
+            let currency = if self.platform == "Manifold" {
+                "Ṁ"
+            } else {
+                "$"
+            };
+            parts.push(format!("on {}{} volume", currency, human_number(volume)));
+        }
+        if let Some(close) = self.meta.close_time {
+            let left = close - self.time;
+            if left < chrono::Duration::zero() {
+                // closed already, nothing to say
+            } else if left < chrono::Duration::days(1) {
+                parts.push("closes within a day".to_string());
+            } else if left < chrono::Duration::days(60) {
+                parts.push(format!("closes in {} days", left.num_days()));
+            }
+        }
+        if parts.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", parts.join(", "))
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let diff = 100.0 * (self.p_after - self.p_before);
         let emoji = if diff >= 0.0 { "📈" } else { "📉" };
-        format!(
-            "{:+.0}% in {} {} {}\n{} #prediction #{}",
-            diff,
-            match self.duration {
-                DiffDuration::Hour => "an hour",
-                DiffDuration::Day => "a day",
-                DiffDuration::Week => "a week",
-            },
-            emoji,
-            self.title,
-            self.url,
-            self.platform,
-        )
+        let duration = &self.duration.label;
+        match &self.scale {
+            Some(scale) => {
+                let value = |x: f32| match scale.kind {
+                    ScaleKind::Date => human_date(scale.value(x)),
+                    _ => human_number(scale.value(x)),
+                };
+                write!(
+                    f,
+                    "{} {} moved from {} to {} in {} {}",
+                    match scale.kind {
+                        ScaleKind::Expected => "expected",
+                        _ => "median",
+                    },
+                    self.title,
+                    value(self.p_before),
+                    value(self.p_after),
+                    duration,
+                    emoji,
+                )?;
+                if let Some((lower, upper)) = self.interval {
+                    write!(f, " (IQR {} to {})", value(lower), value(upper))?;
+                }
+                write!(
+                    f,
+                    "{}\n{} #prediction #{}",
+                    self.context(),
+                    self.url,
+                    self.platform
+                )
+            }
+            None if self.scoring == Scoring::LogOdds => {
+                let ratio = (log_odds(self.p_after) - log_odds(self.p_before)).exp();
+                let (direction, factor) = if ratio >= 1.0 {
+                    ("up", ratio)
+                } else {
+                    ("down", 1.0 / ratio)
+                };
+                let factor = if factor >= 3.0 {
+                    format!("{:.0}", factor)
+                } else {
+                    format!("{:.1}", factor)
+                };
+                write!(
+                    f,
+                    "odds {} {}× ({:.0}% → {:.0}%) in {} {} {}{}\n{} #prediction #{}",
+                    direction,
+                    factor,
+                    100.0 * self.p_before,
+                    100.0 * self.p_after,
+                    duration,
+                    emoji,
+                    self.title,
+                    self.context(),
+                    self.url,
+                    self.platform,
+                )
+            }
+            None => write!(
+                f,
+                "{:+.0}% in {} {} {}{}\n{} #prediction #{}",
+                diff,
+                duration,
+                emoji,
+                self.title,
+                self.context(),
+                self.url,
+                self.platform,
+            ),
+        }
     }
 }
 
-fn diff_factor(d: &DiffDuration) -> f32 {
-    match d {
-        DiffDuration::Hour => 2.0 * 3.0,
-        DiffDuration::Day => 2.0,
-        DiffDuration::Week => 1.0,
+/// Unix timestamp as day
+fn human_date(timestamp: f64) -> String {
+    match DateTime::from_timestamp(timestamp as i64, 0) {
+        Some(t) => t.format("%Y-%m-%d").to_string(),
+        None => "?".to_string(),
     }
 }
 
+/// Short number for humans like 0.25, 12, 3.4k or 1.2M
+fn human_number(v: f64) -> String {
+    let a = v.abs();
+    if a >= 1e9 {
+        format!("{:.1}B", v / 1e9)
+    } else if a >= 1e6 {
+        format!("{:.1}M", v / 1e6)
+    } else if a >= 1e4 {
+        format!("{:.0}k", v / 1e3)
+    } else if a >= 1e3 {
+        format!("{:.1}k", v / 1e3)
+    } else if a >= 10.0 || v.fract() == 0.0 {
+        format!("{:.0}", v)
+    } else {
+        format!("{:.2}", v)
+    }
+}
+
+/// Clamped so that 0% and 100% stay finite
+fn log_odds(p: f32) -> f32 {
+    let p = p.clamp(0.005, 0.995);
+    (p / (1.0 - p)).ln()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::platforms::Platform;
+    use crate::ranking::ScoredRanking;
+    /// Database whose time stands still at now
+    fn frozen(now: DateTime<Utc>) -> Model {
+        Model::with_clock(":memory:", Arc::new(ManualClock::new(now))).expect("open")
+    }
     #[test]
     fn change_comparison() {
-        let a = Change::new_from05(DiffDuration::Day, 0.45);
-        let b = Change::new_from05(DiffDuration::Day, 0.44);
+        let a = Change::new_from05(DiffDuration::day(), 0.45);
+        let b = Change::new_from05(DiffDuration::day(), 0.44);
         assert!(a < b); // -5% < -6% change
-        let c = Change::new_from05(DiffDuration::Day, 0.56);
+        let c = Change::new_from05(DiffDuration::day(), 0.56);
         assert!(a < c); // -5% < +6% change
-        let d = Change::new_from05(DiffDuration::Day, 0.46);
+        let d = Change::new_from05(DiffDuration::day(), 0.46);
         assert!(a > d); // -5% < -4% change
-        let e = Change::new_from05(DiffDuration::Hour, 0.47);
+        let e = Change::new_from05(DiffDuration::hour(), 0.47);
         assert!(a < e); // -5% day < -3% hour
-        let f = Change::new_from05(DiffDuration::Week, 0.1);
+        let f = Change::new_from05(DiffDuration::week(), 0.1);
         assert!(a < f); // -5% day < -40% week
     }
     #[test]
     fn change_comparison2() {
-        let a = Change::new_from05(DiffDuration::Day, 0.7);
-        let b = Change::new_from05(DiffDuration::Week, 0.89);
+        let a = Change::new_from05(DiffDuration::day(), 0.7);
+        let b = Change::new_from05(DiffDuration::week(), 0.89);
         assert!(a > b); // +20 day > +39% week
-        let c = Change::new_from05(DiffDuration::Week, 0.91);
+        let c = Change::new_from05(DiffDuration::week(), 0.91);
         assert!(a < c); // +20 day < +39% week
-        let d = Change::new_from05(DiffDuration::Hour, 0.65);
+        let d = Change::new_from05(DiffDuration::hour(), 0.65);
         assert!(a < d); // +20% day < +15% hour
-        let e = Change::new_from05(DiffDuration::Hour, 0.56);
+        let e = Change::new_from05(DiffDuration::hour(), 0.56);
         assert!(a > e); // +20% day > +6% hour
     }
+    #[test]
+    fn volatility_score() {
+        let mut flat = Change::new_from05(DiffDuration::day(), 0.7);
+        flat.sigma = Some(0.01);
+        let mut wild = Change::new_from05(DiffDuration::day(), 0.7);
+        wild.sigma = Some(0.2);
+        assert_eq!(flat.score(Scoring::Absolute), wild.score(Scoring::Absolute));
+        assert!(flat.score(Scoring::Volatility) > wild.score(Scoring::Volatility));
+        assert_eq!(flat.score(Scoring::Volatility), 10.0); // floored at MIN_SIGMA
+        assert!((wild.score(Scoring::Volatility) - 1.0).abs() < 1e-6);
+        let new = Change::new_from05(DiffDuration::day(), 0.7);
+        assert_eq!(new.score(Scoring::Volatility), 0.0);
+    }
+    #[test]
+    fn volatility_needs_history() {
+        let now = Utc::now();
+        let db = frozen(now);
+        let status = |id: &str, prob: f32| MarketStatus {
+            platform: Platform::Kalshi,
+            id: id.to_string(),
+            prob,
+            url: format!("url {}", id),
+            title: id.to_string(),
+            scale: None,
+            interval: None,
+            meta: Metadata::default(),
+        };
+        // a week of calm hourly history against a market which just appeared
+        for hour in (1..24 * 7).rev() {
+            let time = now - chrono::Duration::hours(hour);
+            let calm = if hour % 2 == 0 { 0.5 } else { 0.51 };
+            db.update_prob(time, &status("calm", calm)).expect("store");
+        }
+        db.update_prob(now - chrono::Duration::days(1), &status("new", 0.5))
+            .expect("store");
+        db.update_prob(now, &status("calm", 0.72)).expect("store");
+        db.update_prob(now, &status("new", 0.57)).expect("store");
+        let ranking = ScoredRanking {
+            scoring: Scoring::Volatility,
+            ..ScoredRanking::default()
+        };
+        let ranked = ranking.rank(
+            db.candidates(&DiffDuration::defaults())
+                .expect("candidates"),
+        );
+        assert_eq!(ranked.first().expect("some").id, "calm");
+        assert!(ranked.iter().all(|c| c.id != "new"));
+    }
+    #[test]
+    fn volatility_ranking_vs_absolute() {
+        let now = Utc::now();
+        let db = frozen(now);
+        let status = |id: &str, prob: f32| MarketStatus {
+            platform: Platform::Manifold,
+            id: id.to_string(),
+            prob,
+            url: format!("url {}", id),
+            title: id.to_string(),
+            scale: None,
+            interval: None,
+            meta: Metadata::default(),
+        };
+        // a week of hourly history, one market swings every hour, the other is flat
+        for hour in (1..24 * 7).rev() {
+            let time = now - chrono::Duration::hours(hour);
+            let swing = if hour % 2 == 0 { 0.3 } else { 0.45 };
+            db.update_prob(time, &status("wild", swing)).expect("store");
+            db.update_prob(time, &status("flat", 0.5)).expect("store");
+        }
+        db.update_prob(now, &status("wild", 0.75)).expect("store");
+        db.update_prob(now, &status("flat", 0.72)).expect("store");
+        let absolute = db
+            .most_noteworthy_change(
+                &ScoredRanking {
+                    scoring: Scoring::Absolute,
+                    ..ScoredRanking::default()
+                },
+                &DiffDuration::defaults(),
+            )
+            .expect("rank");
+        assert_eq!(absolute.expect("some").id, "wild");
+        let volatility = db
+            .most_noteworthy_change(
+                &ScoredRanking {
+                    scoring: Scoring::Volatility,
+                    ..ScoredRanking::default()
+                },
+                &DiffDuration::defaults(),
+            )
+            .expect("rank");
+        assert_eq!(volatility.expect("some").id, "flat");
+    }
+    #[test]
+    fn change_comparison_log_odds() {
+        let mut a = Change::new_from05(DiffDuration::day(), 0.12);
+        a.p_before = 0.02;
+        let b = Change::new_from05(DiffDuration::day(), 0.6);
+        assert!((a.score(Scoring::Absolute) - b.score(Scoring::Absolute)).abs() < 1e-6);
+        assert!(a.score(Scoring::LogOdds) > b.score(Scoring::LogOdds)); // 2%->12% > 50%->60%
+        let c = Change::new_from05(DiffDuration::week(), 0.7);
+        assert!(a.score(Scoring::LogOdds) > c.score(Scoring::LogOdds)); // odds 6.7x day > 2.3x week
+        let mut d = Change::new_from05(DiffDuration::day(), 0.99);
+        d.p_before = 0.9;
+        assert!(d.score(Scoring::LogOdds) > b.score(Scoring::LogOdds)); // 90%->99% > 50%->60%
+        let mut e = Change::new_from05(DiffDuration::day(), 1.0);
+        e.p_before = 0.0;
+        assert!(e.score(Scoring::LogOdds).is_finite());
+        assert_eq!("log-odds".parse::<Scoring>(), Ok(Scoring::LogOdds));
+    }
+    #[test]
+    fn odds_change_text() {
+        let mut c = Change::new_from05(DiffDuration::day(), 0.12);
+        c.p_before = 0.02;
+        c.scoring = Scoring::LogOdds;
+        assert!(c
+            .to_string()
+            .starts_with("odds up 7× (2% → 12%) in a day 📈 title\n"));
+        c.p_before = 0.5;
+        c.p_after = 0.4;
+        assert!(c
+            .to_string()
+            .starts_with("odds down 1.5× (50% → 40%) in a day 📉 title\n"));
+    }
+    #[test]
+    fn custom_windows() {
+        let now = Utc::now();
+        let db = frozen(now);
+        let mut s = MarketStatus {
+            platform: Platform::Kalshi,
+            id: "a".to_string(),
+            prob: 0.2,
+            url: "url".to_string(),
+            title: "title".to_string(),
+            scale: None,
+            interval: None,
+            meta: Metadata::default(),
+        };
+        db.update_prob(now - chrono::Duration::days(3), &s)
+            .expect("store");
+        s.prob = 0.4;
+        db.update_prob(now - chrono::Duration::minutes(16), &s)
+            .expect("store");
+        s.prob = 0.7;
+        db.update_prob(now, &s).expect("store");
+        let quarter = DiffDuration {
+            label: "15 minutes".to_string(),
+            minutes: 15,
+            late: 2,
+            early: 3,
+            weight: 12.0,
+        };
+        let days = DiffDuration {
+            label: "3 days".to_string(),
+            minutes: 3 * 24 * 60,
+            late: 60,
+            early: 60,
+            weight: 1.0,
+        };
+        let windows = [quarter, days];
+        let candidates = db.candidates(&windows).expect("candidates");
+        assert_eq!(candidates.len(), 2);
+        let ranking = ScoredRanking::default();
+        let best = db.most_noteworthy_change(&ranking, &windows).expect("rank");
+        let text = best.expect("some").to_string();
+        assert!(text.starts_with("+30% in 15 minutes"), "{}", text);
+        let best = db
+            .most_noteworthy_change(&ranking, &windows[1..])
+            .expect("rank");
+        let text = best.expect("some").to_string();
+        assert!(text.starts_with("+50% in 3 days"), "{}", text);
+        assert!(db
+            .candidates(&DiffDuration::defaults())
+            .expect("none")
+            .is_empty());
+    }
+    #[test]
+    fn backtest() {
+        let start = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        let db = frozen(start + chrono::Duration::days(2));
+        let mut s = MarketStatus {
+            platform: Platform::Kalshi,
+            id: "a".to_string(),
+            prob: 0.2,
+            url: "url".to_string(),
+            title: "title".to_string(),
+            scale: None,
+            interval: None,
+            meta: Metadata::default(),
+        };
+        // hourly history, the market jumps in hour 10 and again in hour 12
+        for hour in 0..24 {
+            s.prob = match hour {
+                0..=9 => 0.2,
+                10..=11 => 0.5,
+                _ => 0.8,
+            };
+            db.update_prob(start + chrono::Duration::hours(hour), &s)
+                .expect("store");
+        }
+        db.c.execute("INSERT INTO log (time, type, content) VALUES ('2000-01-01 00:00:00', 'pub', 'Kalshi a');")
+            .expect("real publication");
+        let (first, last) = db.history_range().expect("range").expect("some");
+        assert_eq!(last - first, chrono::Duration::hours(23));
+        let ranking = ScoredRanking::default();
+        let windows = [DiffDuration::hour()];
+        let published = db
+            .backtest(first, last, chrono::Duration::hours(1), || {
+                let change = db.most_noteworthy_change(&ranking, &windows)?;
+                let since = db.duration_since_last_publication()?;
+                Ok(change.filter(|_| since >= chrono::Duration::hours(1)))
+            })
+            .expect("backtest");
+        // the second jump is the same market, so only the first one is told
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].0, first + chrono::Duration::hours(10));
+        assert!(published[0].1.to_string().starts_with("+30% in an hour"));
+        // nothing of the replay is kept
+        assert_eq!(count(&db.c, "SELECT COUNT(*) FROM log WHERE type='pub'"), 1);
+        let since = frozen(first).duration_since_last_publication();
+        assert_eq!(since.expect("never"), chrono::Duration::MAX);
+        // a panicking select does not leave the replay clock behind
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+            db.backtest(first, last, chrono::Duration::hours(1), || panic!("select"))
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(db.now(), start + chrono::Duration::days(2));
+    }
+    #[test]
+    fn numeric_change_text() {
+        let mut c = Change::new_from05(DiffDuration::day(), 0.75);
+        c.title = "GitHub stars".to_string();
+        c.scale = Some(Scale {
+            kind: ScaleKind::Expected,
+            min: 0.0,
+            max: 20000.0,
+            zero_point: None,
+        });
+        let text = c.to_string();
+        assert!(text.starts_with("expected GitHub stars moved from 10k to 15k in a day 📈"));
+        c.scale = Some(Scale {
+            kind: ScaleKind::Date,
+            min: 1735689600.0, // 2025-01-01
+            max: 2051222400.0, // 2035-01-01
+            zero_point: None,
+        });
+        c.interval = Some((0.5, 0.9));
+        let text = c.to_string();
+        assert!(
+            text.starts_with("median GitHub stars moved from 2030-01-01 to 2032-07-02 in a day")
+        );
+        assert!(text.contains("(IQR 2030-01-01 to 2033-12-31)"));
+        assert_eq!(human_number(0.25), "0.25");
+        assert_eq!(human_number(2_100_000.0), "2.1M");
+    }
+    #[test]
+    fn numeric_change_without_odds() {
+        let mut c = Change::new_from(DiffDuration::day(), 0.02, 0.12);
+        c.scale = Some(Scale {
+            kind: ScaleKind::Expected,
+            min: 0.0,
+            max: 20000.0,
+            zero_point: None,
+        });
+        assert_eq!(c.score(Scoring::LogOdds), c.score(Scoring::Absolute));
+        assert_eq!(c.moved(Scoring::LogOdds), c.moved(Scoring::Absolute));
+        let log_odds = ScoredRanking {
+            scoring: Scoring::LogOdds,
+            ..ScoredRanking::default()
+        };
+        // a tenth of the range is no big move, even near its end
+        assert!(log_odds.rank(vec![c.clone()]).is_empty());
+        c.p_after = 0.5;
+        let ranked = log_odds.rank(vec![c]);
+        assert_eq!(ranked.len(), 1);
+        assert!(ranked[0]
+            .to_string()
+            .starts_with("expected title moved from"));
+    }
+    #[test]
+    fn context_text() {
+        let mut c = Change::new_from05(DiffDuration::day(), 0.75);
+        c.platform = "Polymarket".to_string();
+        c.meta.volume = Some(2_000_000.0);
+        c.meta.close_time = Some(c.time + chrono::Duration::hours(3 * 24 + 5));
+        let text = c.to_string();
+        assert!(text.starts_with("+25% in a day 📈 title on $2.0M volume, closes in 3 days\n"));
+        c.platform = "Manifold".to_string();
+        c.meta.close_time = Some(c.time - chrono::Duration::hours(1));
+        assert!(c.to_string().contains("title on Ṁ2.0M volume\n"));
+    }
+    #[test]
+    fn metadata_roundtrip() {
+        let db = Model::new(":memory:").expect("open");
+        let close = Utc.with_ymd_and_hms(2027, 1, 1, 4, 59, 0).unwrap();
+        let s = MarketStatus {
+            platform: Platform::Kalshi,
+            id: "KXRECSSNBER-26".to_string(),
+            prob: 0.235,
+            url: "url".to_string(),
+            title: "Recession in 2026?".to_string(),
+            scale: None,
+            interval: None,
+            meta: Metadata {
+                volume: Some(2514032.0),
+                liquidity: Some(154802.13),
+                bettors: None,
+                close_time: Some(close),
+                outcome_type: Some("binary".to_string()),
+            },
+        };
+        db.update_prob(Utc::now(), &s).expect("store");
+        let (_, title, meta) = get_details(&db.c, "Kalshi", "KXRECSSNBER-26").expect("details");
+        assert_eq!(title, "Recession in 2026?");
+        assert_eq!(meta, s.meta);
+    }
+    fn count(c: &Connection, query: &str) -> i64 {
+        let mut s = c.prepare(query).expect("prepare");
+        s.next().expect("row");
+        s.read::<i64, _>(0).expect("count")
+    }
+    #[test]
+    fn migrate_historical_schemas() {
+        // the released schema, then each version on the way from it
+        for step in 1..=SCHEMA_VERSION {
+            let c = sqlite::open(":memory:").expect("open");
+            c.execute(include_str!("../fixtures/schema/v1.sql"))
+                .expect("fixture");
+            assert_eq!(schema_version(&c).expect("version"), 1);
+            migrate_to(&c, step).expect("step");
+            assert_eq!(schema_version(&c).expect("version"), step);
+            migrate(&c).expect("migrate");
+            assert_eq!(schema_version(&c).expect("version"), SCHEMA_VERSION);
+            assert_eq!(count(&c, "SELECT COUNT(*) FROM probabilities"), 3);
+            assert_eq!(count(&c, "SELECT COUNT(*) FROM details"), 2, "v{}", step);
+            let (_, title, _) = get_details(&c, "Manifold", "a").expect("details");
+            assert_eq!(title, "New title");
+            assert_eq!(
+                count(&c, "SELECT COUNT(*) FROM title_history WHERE id='a'"),
+                2
+            );
+            assert_eq!(
+                last_publications(&c, Utc::now()).expect("log"),
+                vec!["Manifold a".to_string()]
+            );
+            migrate(&c).expect("nothing to do");
+            assert_eq!(count(&c, "SELECT COUNT(*) FROM details_history"), 3);
+            let untimed = "SELECT COUNT(*) FROM details_history WHERE time IS NULL";
+            assert_eq!(count(&c, untimed), 0, "v{}", step);
+            let earliest = "SELECT COUNT(*) FROM details_history WHERE id='a'
+                AND time = (SELECT MIN(time) FROM probabilities WHERE id='a')";
+            assert_eq!(count(&c, earliest), 2, "v{}", step);
+        }
+    }
+    #[test]
+    fn refuse_newer_schema() {
+        let c = sqlite::open(":memory:").expect("open");
+        migrate(&c).expect("fresh");
+        assert_eq!(schema_version(&c).expect("version"), SCHEMA_VERSION);
+        c.execute(format!("PRAGMA user_version = {};", SCHEMA_VERSION + 1))
+            .expect("bump");
+        assert!(matches!(migrate(&c), Err(ModelError::Schema(_))));
+    }
+    #[test]
+    fn corrupt_time() {
+        let db = Model::new(":memory:").expect("open");
+        db.c.execute("INSERT INTO log (time, type, content) VALUES ('yesterday', 'pub', 'x');")
+            .expect("insert");
+        let since = db.duration_since_last_publication();
+        assert!(matches!(since, Err(ModelError::Corrupt(_))));
+    }
+    #[test]
+    fn deliveries() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let db = frozen(now);
+        db.c.execute("DELETE FROM log;").expect("empty");
+        let mut c = Change::new_from05(DiffDuration::day(), 0.8);
+        c.platform = "Kalshi".to_string();
+        db.log_delivery("mastodon", &c, Ok(None)).expect("ok");
+        db.log_delivery("bluesky", &c, Err("status 502".to_string()))
+            .expect("failed");
+        db.log_delivery("telegram", &c, Ok(Some("41".to_string())))
+            .expect("ok");
+        db.log_delivery("telegram", &c, Ok(Some("42".to_string())))
+            .expect("ok");
+        assert_eq!(db.last_receipt("mastodon", &c).expect("none"), None);
+        assert_eq!(db.last_receipt("bluesky", &c).expect("none"), None);
+        let receipt = db.last_receipt("telegram", &c).expect("some");
+        assert_eq!(
+            receipt,
+            Some(Receipt {
+                id: "42".to_string(),
+                prob: Some(0.8)
+            })
+        );
+        assert_eq!(
+            count(
+                &db.c,
+                "SELECT COUNT(*) FROM log WHERE content='mastodon Kalshi id'"
+            ),
+            1
+        );
+        assert_eq!(
+            count(&db.c, "SELECT COUNT(*) FROM log WHERE type='target-failed' AND content='bluesky Kalshi id: status 502'"),
+            1
+        );
+        // only the publication itself starts the silence
+        assert_eq!(
+            db.duration_since_last_publication().expect("never"),
+            chrono::Duration::MAX
+        );
+        db.log_publication(c).expect("pub");
+        assert_eq!(
+            db.duration_since_last_publication().expect("now"),
+            chrono::Duration::zero()
+        );
+    }
+    #[test]
+    fn follow_up() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let clock = Arc::new(ManualClock::new(now));
+        let db = Model::with_clock(":memory:", clock.clone()).expect("open");
+        let mut s = MarketStatus {
+            platform: Platform::Kalshi,
+            id: "a".to_string(),
+            prob: 0.2,
+            url: "url".to_string(),
+            title: "title".to_string(),
+            scale: None,
+            interval: None,
+            meta: Metadata::default(),
+        };
+        db.update_prob(now - chrono::Duration::hours(1), &s)
+            .expect("store");
+        s.prob = 0.7;
+        db.update_prob(now, &s).expect("store");
+        let ranking = ScoredRanking::default();
+        let windows = [DiffDuration::hour()];
+        assert_eq!(db.follow_up(&ranking, &windows).expect("nothing"), None);
+        let change = db.most_noteworthy_change(&ranking, &windows).expect("rank");
+        db.log_publication(change.expect("some")).expect("pub");
+        assert!(db.candidates(&windows).expect("published").is_empty());
+        let again = db.follow_up(&ranking, &windows).expect("rank");
+        let again = again.expect("same market");
+        assert_eq!(again.id, "a");
+        db.log_delivery("telegram", &again, Ok(Some("42".to_string())))
+            .expect("delivered");
+        // the post tells 70% already, so the next run leaves it alone
+        let outdated = db.outdated_receipt(&ranking, "telegram", &again);
+        assert_eq!(outdated.expect("none"), None);
+        clock.advance(chrono::Duration::minutes(30));
+        s.prob = 0.95;
+        db.update_prob(now + chrono::Duration::minutes(30), &s)
+            .expect("store");
+        let moved = db.follow_up(&ranking, &windows).expect("rank");
+        let moved = moved.expect("moved again");
+        let outdated = db.outdated_receipt(&ranking, "telegram", &moved);
+        assert_eq!(outdated.expect("some").as_deref(), Some("42"));
+        db.log_delivery("telegram", &moved, Ok(Some("42".to_string())))
+            .expect("edited");
+        let outdated = db.outdated_receipt(&ranking, "telegram", &moved);
+        assert_eq!(outdated.expect("none"), None);
+    }
+    #[test]
+    fn nested_transactions() {
+        let db = Model::new(":memory:").expect("open");
+        let insert = |content: &str| -> Result<(), ModelError> {
+            let mut s =
+                db.c.prepare("INSERT INTO log (type, content) VALUES ('test', ?);")?;
+            s.bind((1, content))?;
+            s.next()?;
+            Ok(())
+        };
+        let n = db
+            .transact(|| {
+                insert("outer")?;
+                let inner: Result<(), ModelError> = db.transact(|| {
+                    insert("inner")?;
+                    Err(ModelError::Corrupt("fail".to_string()))
+                });
+                assert!(inner.is_err());
+                db.transact(|| insert("second"))?;
+                Ok::<i32, ModelError>(42)
+            })
+            .expect("commit");
+        assert_eq!(n, 42);
+        let tests = "SELECT COUNT(*) FROM log WHERE type='test'";
+        assert_eq!(count(&db.c, tests), 2);
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+            db.transact(|| -> Result<(), ModelError> {
+                insert("lost")?;
+                panic!("mid-fetch");
+            })
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(count(&db.c, tests), 2);
+        db.transact(|| insert("after")).expect("usable again");
+        assert_eq!(count(&db.c, tests), 3);
+    }
+    #[test]
+    fn compaction() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let db = frozen(now);
+        let mut s = MarketStatus {
+            platform: Platform::Manifold,
+            id: "a".to_string(),
+            prob: 0.0,
+            url: "url".to_string(),
+            title: "title".to_string(),
+            scale: None,
+            interval: None,
+            meta: Metadata::default(),
+        };
+        for (days, hour, minute, prob) in [
+            (30, 10, 5, 0.2),
+            (30, 10, 20, 0.5),
+            (30, 10, 50, 0.3),
+            (30, 11, 10, 0.4),
+            (15, 9, 0, 0.6),
+            (15, 9, 30, 0.7),
+            (1, 9, 0, 0.9),
+        ] {
+            let day = now - chrono::Duration::days(days);
+            s.prob = prob;
+            let time = day.with_hour(hour).unwrap().with_minute(minute).unwrap();
+            db.update_prob(time, &s).expect("store");
+        }
+        let r = Retention {
+            full_days: 10,
+            hourly_days: 10,
+        };
+        assert_eq!(db.compact(&r).expect("compact"), (6, 2));
+        assert_eq!(count(&db.c, "SELECT COUNT(*) FROM probabilities"), 1);
+        let row = |query: &str| {
+            let mut st = db.c.prepare(query).expect("prepare");
+            assert_eq!(st.next().expect("row"), sqlite::State::Row);
+            let cols: Vec<f32> = (0..4)
+                .map(|i| st.read::<f64, _>(i).expect("col") as f32)
+                .collect();
+            cols
+        };
+        assert_eq!(
+            row("SELECT open, close, min, max FROM probabilities_daily WHERE bucket='2026-01-30 00:00:00'"),
+            vec![0.2, 0.4, 0.2, 0.5]
+        );
+        assert_eq!(
+            row("SELECT open, close, min, max FROM probabilities_hourly WHERE bucket='2026-02-14 09:00:00'"),
+            vec![0.6, 0.7, 0.6, 0.7]
+        );
+        assert_eq!(db.compact(&r).expect("again"), (0, 0));
+        // a late row for an hour which is compacted already extends its bucket
+        s.prob = 0.1;
+        let late = (now - chrono::Duration::days(15))
+            .with_hour(9)
+            .unwrap()
+            .with_minute(45)
+            .unwrap();
+        db.update_prob(late, &s).expect("store late");
+        assert_eq!(db.compact(&r).expect("extend"), (1, 0));
+        assert_eq!(
+            row("SELECT open, close, min, max FROM probabilities_hourly WHERE bucket='2026-02-14 09:00:00'"),
+            vec![0.6, 0.1, 0.1, 0.7]
+        );
+        assert_eq!(count(&db.c, "SELECT COUNT(*) FROM probabilities_hourly"), 1);
+    }
+    #[test]
+    fn details_upsert() {
+        let db = Model::new(":memory:").expect("open");
+        let mut s = MarketStatus {
+            platform: Platform::Metaculus,
+            id: "3479".to_string(),
+            prob: 0.25,
+            url: "url".to_string(),
+            title: "Human on Mars before 2040?".to_string(),
+            scale: None,
+            interval: None,
+            meta: Metadata::default(),
+        };
+        db.update_prob(Utc::now(), &s).expect("store");
+        db.update_prob(Utc::now(), &s).expect("store");
+        s.title = "Will there be a human on Mars before 2040?".to_string();
+        db.update_prob(Utc::now(), &s).expect("store");
+        assert_eq!(count(&db.c, "SELECT COUNT(*) FROM details"), 1);
+        assert_eq!(count(&db.c, "SELECT COUNT(*) FROM title_history"), 2);
+        let (_, title, _) = get_details(&db.c, "Metaculus", "3479").expect("details");
+        assert_eq!(title, s.title);
+    }
+    #[test]
+    fn group_dedup() {
+        let previous = vec!["Polymarket nyc-mayor-2025".to_string()];
+        let mut c = Change::new_from05(DiffDuration::day(), 0.8);
+        c.platform = "Polymarket".to_string();
+        c.id = "nyc-mayor-2025 will-andrew-cuomo-win-nyc-mayor".to_string();
+        assert!(is_published(&c, &previous)); // same event was published already
+        c.id = "nyc-mayor-2025-turnout".to_string();
+        assert!(!is_published(&c, &previous)); // only shares a prefix
+    }
 }
 
 fn insert_probability(
     c: &Connection,
-    prob: f32,
     platform: &str,
-    id: &str,
-    title: &str,
-    url: &str,
+    s: &MarketStatus,
     time: &DateTime<Utc>,
 ) -> Result<String, sqlite::Error> {
     // now insert new probability
-    let query = "INSERT INTO probabilities (prob,platform,id,time) VALUES (?,?,?,?);";
+    let query = "INSERT INTO probabilities (prob,platform,id,time,lower,upper,volume,liquidity) VALUES (?,?,?,?,?,?,?,?);";
     let mut stmt = c.prepare(query)?;
-    stmt.bind((1, prob as f64))?;
+    stmt.bind((1, s.prob as f64))?;
     stmt.bind((2, platform))?;
-    stmt.bind((3, id))?;
-    let t: String = time.format("%Y-%m-%d %H:%M:%S").to_string();
+    stmt.bind((3, s.id.as_str()))?;
+    let t = db_time(time);
     stmt.bind((4, t.as_str()))?;
+    stmt.bind((5, s.interval.map(|i| i.0 as f64)))?;
+    stmt.bind((6, s.interval.map(|i| i.1 as f64)))?;
+    stmt.bind((7, s.meta.volume))?;
+    stmt.bind((8, s.meta.liquidity))?;
+    stmt.next()?;
+    upsert_details(c, platform, s, &t)?;
+    Result::Ok("good".to_string())
+}
+
+/// Keep one details row per market, earlier titles and urls go to the history
+fn upsert_details(
+    c: &Connection,
+    platform: &str,
+    s: &MarketStatus,
+    time: &str,
+) -> Result<(), sqlite::Error> {
+    let query = "SELECT title, url FROM details WHERE platform=? AND id=?;";
+    let mut stmt = c.prepare(query)?;
+    stmt.bind((1, platform))?;
+    stmt.bind((2, s.id.as_str()))?;
+    let changed = match stmt.next()? {
+        sqlite::State::Row => {
+            stmt.read::<String, _>("title")? != s.title || stmt.read::<String, _>("url")? != s.url
+        }
+        sqlite::State::Done => true,
+    };
+    if changed {
+        let query = "INSERT INTO details_history (time,platform,id,title,url) VALUES(?,?,?,?,?);";
+        let mut stmt = c.prepare(query)?;
+        stmt.bind((1, time))?;
+        stmt.bind((2, platform))?;
+        stmt.bind((3, s.id.as_str()))?;
+        stmt.bind((4, s.title.as_str()))?;
+        stmt.bind((5, s.url.as_str()))?;
+        stmt.next()?;
+    }
+    let query = "INSERT INTO details (platform,id,title,url,volume,liquidity,bettors,close_time,outcome_type) VALUES(?,?,?,?,?,?,?,?,?)
+        ON CONFLICT(platform, id) DO UPDATE SET title=excluded.title, url=excluded.url,
+        volume=excluded.volume, liquidity=excluded.liquidity, bettors=excluded.bettors,
+        close_time=excluded.close_time, outcome_type=excluded.outcome_type;";
+    let mut stmt = c.prepare(query)?;
+    stmt.bind((1, platform))?;
+    stmt.bind((2, s.id.as_str()))?;
+    stmt.bind((3, s.title.as_str()))?;
+    stmt.bind((4, s.url.as_str()))?;
+    stmt.bind((5, s.meta.volume))?;
+    stmt.bind((6, s.meta.liquidity))?;
+    stmt.bind((7, s.meta.bettors))?;
+    let close = s
+        .meta
+        .close_time
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string());
+    stmt.bind((8, close.as_deref()))?;
+    stmt.bind((9, s.meta.outcome_type.as_deref()))?;
     stmt.next()?;
-    // save details
-    let query = "INSERT INTO details (platform,id,title,url) VALUES(?,?,?,?);";
+    Ok(())
+}
+
+fn insert_scale(
+    c: &Connection,
+    platform: &str,
+    id: &str,
+    scale: &Scale,
+) -> Result<String, sqlite::Error> {
+    let query =
+        "INSERT OR REPLACE INTO scales (platform,id,min,max,zero_point,kind) VALUES (?,?,?,?,?,?);";
     let mut stmt = c.prepare(query)?;
     stmt.bind((1, platform))?;
     stmt.bind((2, id))?;
-    stmt.bind((3, title))?;
-    stmt.bind((4, url))?;
+    stmt.bind((3, scale.min))?;
+    stmt.bind((4, scale.max))?;
+    stmt.bind((5, scale.zero_point))?;
+    stmt.bind((6, scale.kind.to_string().as_str()))?;
     stmt.next()?;
     Result::Ok("good".to_string())
 }
 
-fn previous_probability(c: &Connection, platform: &str, id: &str) -> Option<f32> {
+fn get_scale(c: &Connection, platform: &str, id: &str) -> Result<Option<Scale>, ModelError> {
+    let query = "SELECT min, max, zero_point, kind FROM scales WHERE platform=? AND id=?;";
+    let mut s = c.prepare(query)?;
+    s.bind((1, platform))?;
+    s.bind((2, id))?;
+    if let sqlite::State::Row = s.next()? {
+        let kind = match s.read::<Option<String>, _>("kind")? {
+            Some(k) => k.parse().map_err(ModelError::Corrupt)?,
+            None => ScaleKind::Expected,
+        };
+        Ok(Some(Scale {
+            kind,
+            min: s.read::<f64, _>("min")?,
+            max: s.read::<f64, _>("max")?,
+            zero_point: s.read::<Option<f64>, _>("zero_point")?,
+        }))
+    } else {
+        Ok(Option::None)
+    }
+}
+
+fn previous_probability(
+    c: &Connection,
+    platform: &str,
+    id: &str,
+) -> Result<Option<f32>, ModelError> {
     let check =
         "SELECT prob FROM probabilities WHERE platform = ? AND id = ? ORDER BY time DESC LIMIT 1;";
-    let mut s = c.prepare(check).ok()?;
-    s.bind((1, platform)).ok()?;
-    s.bind((2, id)).ok()?;
-    if sqlite::State::Row == s.next().ok()? {
-        Some(s.read::<f64, _>("prob").ok()? as f32)
+    let mut s = c.prepare(check)?;
+    s.bind((1, platform))?;
+    s.bind((2, id))?;
+    if sqlite::State::Row == s.next()? {
+        Ok(Some(s.read::<f64, _>("prob")? as f32))
     } else {
-        Option::None
+        Ok(Option::None)
     }
 }
 
@@ -268,9 +1646,8 @@ struct Timestamp {
     platform: String,
     id: String,
     latest: String,
-    hour: Option<String>,
-    day: Option<String>,
-    week: Option<String>,
+    /// Earlier observation for each window, if there is one
+    earlier: Vec<Option<String>>,
 }
 
 impl Timestamp {
@@ -280,13 +1657,20 @@ impl Timestamp {
         p_now: f32,
         t: Option<String>,
         duration: DiffDuration,
-    ) -> Option<Change> {
-        let ts = t?;
+        now: DateTime<Utc>,
+    ) -> Result<Option<Change>, ModelError> {
+        let Some(ts) = t else {
+            return Ok(Option::None);
+        };
         let platform = self.platform.as_str();
         let id = self.id.as_str();
-        let p_before = get_prob_by_time(c, platform, id, &ts)?;
-        let u_t = get_details(c, platform, id);
-        Option::Some(Change {
+        let Some(p_before) = get_prob_by_time(c, platform, id, &ts)? else {
+            return Ok(Option::None);
+        };
+        let u_t = get_details(c, platform, id)?;
+        let sigma = variance_per_hour(c, platform, id, &ts)?
+            .map(|v| (v * duration.minutes as f64 / 60.0).sqrt() as f32);
+        Ok(Option::Some(Change {
             platform: self.platform.clone(),
             id: self.id.clone(),
             duration,
@@ -294,120 +1678,341 @@ impl Timestamp {
             p_after: p_now,
             url: u_t.0,
             title: u_t.1,
-        })
+            scale: get_scale(c, platform, id)?,
+            interval: get_interval_by_time(c, platform, id, &self.latest)?,
+            meta: u_t.2,
+            sigma,
+            scoring: Scoring::Absolute,
+            time: now,
+        }))
     }
 }
 
-fn get_prob_by_time(c: &Connection, platform: &str, id: &str, time: &str) -> Option<f32> {
+/// Realized variance of the probability per hour until time,
+/// random walk style so a window of n hours has n times the variance.
+fn variance_per_hour(
+    c: &Connection,
+    platform: &str,
+    id: &str,
+    time: &str,
+) -> Result<Option<f64>, ModelError> {
+    let query = "SELECT SUM(d * d) AS sum_sq, SUM(dt) AS hours, COUNT(d) AS n FROM (
+        SELECT prob - LAG(prob) OVER w AS d,
+        (julianday(time) - julianday(LAG(time) OVER w)) * 24 AS dt
+        FROM probabilities WHERE platform=? AND id=? AND time<=?
+        WINDOW w AS (ORDER BY time));";
+    let mut s = c.prepare(query)?;
+    s.bind((1, platform))?;
+    s.bind((2, id))?;
+    s.bind((3, time))?;
+    s.next()?;
+    let n = s.read::<i64, _>("n")?;
+    let sum_sq = s.read::<Option<f64>, _>("sum_sq")?;
+    let hours = s.read::<Option<f64>, _>("hours")?;
+    match (sum_sq, hours) {
+        (Some(sum_sq), Some(hours)) if n >= 3 && hours > 0.0 => Ok(Some(sum_sq / hours)),
+        _ => Ok(None), // too little history
+    }
+}
+
+fn get_prob_by_time(
+    c: &Connection,
+    platform: &str,
+    id: &str,
+    time: &str,
+) -> Result<Option<f32>, ModelError> {
     let query = "SELECT prob FROM probabilities WHERE platform=? AND id=? AND time=?;";
-    let mut s = c.prepare(query).ok()?;
-    s.bind((1, platform)).ok();
-    s.bind((2, id)).ok();
-    s.bind((3, time)).ok();
-    if let Ok(sqlite::State::Row) = s.next() {
-        if let Ok(prob) = s.read::<f64, _>("prob") {
-            Option::Some(prob as f32)
-        } else {
-            Option::None
-        }
+    let mut s = c.prepare(query)?;
+    s.bind((1, platform))?;
+    s.bind((2, id))?;
+    s.bind((3, time))?;
+    if let sqlite::State::Row = s.next()? {
+        Ok(Some(s.read::<f64, _>("prob")? as f32))
     } else {
-        Option::None
+        Ok(Option::None)
     }
 }
 
-fn get_details(c: &Connection, platform: &str, id: &str) -> (String, String) {
-    let query = "SELECT url, title FROM details WHERE platform=? AND id=?;";
-    let mut s = c.prepare(query).expect("prepare");
-    s.bind((1, platform)).expect("bind 1");
-    s.bind((2, id)).expect("bind 2");
-    if let Ok(sqlite::State::Row) = s.next() {
-        let url = s.read::<String, _>("url").expect("url");
-        let title = s.read::<String, _>("title").expect("title");
-        (url, title)
+fn get_interval_by_time(
+    c: &Connection,
+    platform: &str,
+    id: &str,
+    time: &str,
+) -> Result<Option<(f32, f32)>, ModelError> {
+    let query = "SELECT lower, upper FROM probabilities WHERE platform=? AND id=? AND time=?;";
+    let mut s = c.prepare(query)?;
+    s.bind((1, platform))?;
+    s.bind((2, id))?;
+    s.bind((3, time))?;
+    if let sqlite::State::Row = s.next()? {
+        let lower = s.read::<Option<f64>, _>("lower")?;
+        let upper = s.read::<Option<f64>, _>("upper")?;
+        Ok(lower.zip(upper).map(|(l, u)| (l as f32, u as f32)))
     } else {
-        ("?".to_string(), "??".to_string())
+        Ok(Option::None)
     }
 }
 
-fn query_timestamps(c: &Connection, minutes_ago: chrono::Duration) -> Vec<Timestamp> {
+fn get_details(
+    c: &Connection,
+    platform: &str,
+    id: &str,
+) -> Result<(String, String, Metadata), ModelError> {
+    let query = "SELECT url, title, volume, liquidity, bettors, close_time, outcome_type FROM details WHERE platform=? AND id=?;";
+    let mut s = c.prepare(query)?;
+    s.bind((1, platform))?;
+    s.bind((2, id))?;
+    if let sqlite::State::Row = s.next()? {
+        let url = s.read::<String, _>("url")?;
+        let title = s.read::<String, _>("title")?;
+        let close = match s.read::<Option<String>, _>("close_time")? {
+            Some(t) => Some(parse_db_time(&t)?),
+            None => None,
+        };
+        let meta = Metadata {
+            volume: s.read::<Option<f64>, _>("volume")?,
+            liquidity: s.read::<Option<f64>, _>("liquidity")?,
+            bettors: s.read::<Option<i64>, _>("bettors")?,
+            close_time: close,
+            outcome_type: s.read::<Option<String>, _>("outcome_type")?,
+        };
+        Ok((url, title, meta))
+    } else {
+        Ok(("?".to_string(), "??".to_string(), Metadata::default()))
+    }
+}
+
+/// Windows are relative to the latest update, which was minutes_ago
+fn query_timestamps(
+    c: &Connection,
+    minutes_ago: chrono::Duration,
+    windows: &[DiffDuration],
+    now: DateTime<Utc>,
+) -> Result<Vec<Timestamp>, ModelError> {
     let mut ret = vec![];
     let min = minutes_ago.num_minutes();
-    let query = format!(
+    let mut query = format!(
         "SELECT platform, id,
-MAX(CASE WHEN time >= DATETIME(CURRENT_TIMESTAMP, '-{} minutes') THEN time END) AS latest_time,
-MAX(CASE WHEN time <= DATETIME(CURRENT_TIMESTAMP, '-{} minutes') AND time >= DATETIME(CURRENT_TIMESTAMP, '-{} minutes')THEN time END) AS time_1_hour_ago,
-MAX(CASE WHEN time <= DATETIME(CURRENT_TIMESTAMP, '-22 hours') AND time >= DATETIME(CURRENT_TIMESTAMP, '-28 hours') THEN time END) AS time_1_day_ago,
-MAX(CASE WHEN time <= DATETIME(CURRENT_TIMESTAMP, '-6 day') AND time >= DATETIME(CURRENT_TIMESTAMP, '-8 days') THEN time END) AS time_1_week_ago
-FROM probabilities
-GROUP BY platform, id;",
-         min+10, min+55, min+99
+MAX(CASE WHEN time >= DATETIME(?1, '-{} minutes') THEN time END) AS latest_time",
+        min + 10
     );
-    let mut s = c.prepare(query).expect("query bound");
-    while let Ok(sqlite::State::Row) = s.next() {
-        let latest = match s.read::<String, _>("latest_time") {
-            Result::Ok(x) => x,
-            Result::Err(_) => {
+    for (i, w) in windows.iter().enumerate() {
+        query += &format!(
+            ",\nMAX(CASE WHEN time <= DATETIME(?1, '-{} minutes') AND time >= DATETIME(?1, '-{} minutes') THEN time END) AS earlier_{}",
+            min + w.minutes - w.late,
+            min + w.minutes + w.early,
+            i
+        );
+    }
+    query += "\nFROM probabilities WHERE time <= ?1\nGROUP BY platform, id;";
+    let mut s = c.prepare(query)?;
+    s.bind((1, db_time(&now).as_str()))?;
+    while let sqlite::State::Row = s.next()? {
+        let latest = match s.read::<Option<String>, _>("latest_time")? {
+            Some(x) => x,
+            None => {
                 continue; // no latest value
             }
         };
-        let hour = s.read::<String, _>("time_1_hour_ago").ok();
-        let day = s.read::<String, _>("time_1_day_ago").ok();
-        let week = s.read::<String, _>("time_1_week_ago").ok();
-        if hour.is_none() && day.is_none() && week.is_none() {
+        let mut earlier = vec![];
+        for i in 0..windows.len() {
+            earlier.push(s.read::<Option<String>, _>(format!("earlier_{}", i).as_str())?);
+        }
+        if earlier.iter().all(|t| t.is_none()) {
             continue; // no previous data about this market
         }
         let timestamps = Timestamp {
-            platform: s.read::<String, _>("platform").expect("field"),
-            id: s.read::<String, _>("id").expect("id field"),
+            platform: s.read::<String, _>("platform")?,
+            id: s.read::<String, _>("id")?,
             latest,
-            hour,
-            day,
-            week,
+            earlier,
         };
         ret.push(timestamps);
     }
-    ret
+    Ok(ret)
 }
 
-fn last_publications(c: &Connection) -> Vec<String> {
-    let query = "SELECT content FROM log WHERE type = 'pub' ORDER BY time DESC LIMIT 30;";
-    let mut s = c.prepare(query).expect("query bound");
+fn last_publications(c: &Connection, now: DateTime<Utc>) -> Result<Vec<String>, ModelError> {
+    let query =
+        "SELECT content FROM log WHERE type = 'pub' AND time <= ? ORDER BY time DESC LIMIT 30;";
+    let mut s = c.prepare(query)?;
+    s.bind((1, db_time(&now).as_str()))?;
     let mut ret: Vec<String> = vec![];
-    while let Ok(sqlite::State::Row) = s.next() {
-        ret.push(s.read::<String, _>("content").expect("content"));
+    while let sqlite::State::Row = s.next()? {
+        ret.push(s.read::<String, _>("content")?);
     }
-    ret
+    Ok(ret)
+}
+
+fn is_published(c: &Change, previous: &[String]) -> bool {
+    previous.contains(&group_key(&c.platform, &c.id))
+}
+
+/// Multiple-choice markets and market groups get a postfix for each answer.
+/// Ignoring the postfix makes a whole group count as published at once.
+fn group_key(platform: &str, id: &str) -> String {
+    let group = id.split_ascii_whitespace().next().unwrap_or(id);
+    format!("{} {}", platform, group)
 }
 
-fn set_if_not_published(a: &mut Change, b: Option<Change>, previous: &Vec<String>) {
-    if b.is_none() {
-        return; // is none
+/// One step of the schema, applied in order and only once per database
+struct Migration {
+    version: i64,
+    up: fn(&Connection) -> Result<(), sqlite::Error>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        up: base_schema,
+    },
+    Migration {
+        version: 2,
+        up: numeric_markets,
+    },
+    Migration {
+        version: 3,
+        up: market_metadata,
+    },
+    Migration {
+        version: 4,
+        up: unique_details,
+    },
+    Migration {
+        version: 5,
+        up: aggregated_probabilities,
+    },
+    Migration {
+        version: 6,
+        up: delivery_receipts,
+    },
+];
+
+/// Schema version this binary writes
+const SCHEMA_VERSION: i64 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+fn schema_version(c: &Connection) -> Result<i64, sqlite::Error> {
+    let mut s = c.prepare("PRAGMA user_version;")?;
+    s.next()?;
+    let version = s.read::<i64, _>(0)?;
+    if version > 0 {
+        return Ok(version);
     }
-    let next = b.expect("is some");
-    if a > &mut next.clone() {
-        return; // a is better already
+    // databases from before versioning have the log table at least
+    let mut s = c.prepare("SELECT name FROM sqlite_master WHERE type='table' AND name='log';")?;
+    match s.next()? {
+        sqlite::State::Row => Ok(1),
+        sqlite::State::Done => Ok(0),
     }
-    let next_id = format!("{} {}", next.platform, next.id);
-    for p in previous {
-        if next_id.starts_with(p) {
-            return; // already published
+}
+
+/// Bring the database up to SCHEMA_VERSION, each step in its own transaction
+fn migrate(c: &Connection) -> Result<(), ModelError> {
+    migrate_to(c, SCHEMA_VERSION)
+}
+
+fn migrate_to(c: &Connection, target: i64) -> Result<(), ModelError> {
+    let current = schema_version(c)?;
+    if current > SCHEMA_VERSION {
+        return Err(ModelError::Schema(format!(
+            "database schema version {} is newer than {} supported by this binary",
+            current, SCHEMA_VERSION
+        )));
+    }
+    for m in MIGRATIONS
+        .iter()
+        .filter(|m| m.version > current && m.version <= target)
+    {
+        info!("migrating database to version {}", m.version);
+        let step = || -> Result<(), sqlite::Error> {
+            c.execute("BEGIN TRANSACTION;")?;
+            (m.up)(c)?;
+            c.execute(format!("PRAGMA user_version = {};", m.version))?;
+            c.execute("COMMIT;")
+        };
+        if let Err(e) = step() {
+            c.execute("ROLLBACK;").ok();
+            return Err(ModelError::Schema(format!(
+                "migration to version {} failed: {}",
+                m.version, e
+            )));
         }
     }
-    debug!("do set {}-{}", next.p_before, next.p_after);
-    a.clone_from(&next);
+    Ok(())
 }
 
-fn init_tables(c: &Connection) {
-    let check_first_q = "SELECT name FROM sqlite_master WHERE type='table' AND name='log';";
-    let mut s = c.prepare(check_first_q).expect("prep check");
-    if let Ok(sqlite::State::Row) = s.next() {
-        return; // already initalized
-    }
-    let query = "
-    CREATE TABLE log (time DATETIME DEFAULT CURRENT_TIMESTAMP, type TEXT, content TEXT);
-    INSERT INTO log (type, content) VALUES (\"creation\", \"hello world\");
-    CREATE TABLE probabilities(time DATETIME DEFAULT CURRENT_TIMESTAMP, platform TEXT, id TEXT, prob REAL);
-    CREATE INDEX idx_probabilities_platform_id_time ON probabilities(platform, id, time);
-    CREATE TABLE details (platform TEXT, id TEXT, title TEXT, url TEXT);";
-    c.execute(query).expect("sql init");
+fn base_schema(c: &Connection) -> Result<(), sqlite::Error> {
+    c.execute(
+        "
+        CREATE TABLE log (time DATETIME DEFAULT CURRENT_TIMESTAMP, type TEXT, content TEXT);
+        INSERT INTO log (type, content) VALUES (\"creation\", \"hello world\");
+        CREATE TABLE probabilities(time DATETIME DEFAULT CURRENT_TIMESTAMP, platform TEXT, id TEXT, prob REAL);
+        CREATE INDEX idx_probabilities_platform_id_time ON probabilities(platform, id, time);
+        CREATE TABLE details (platform TEXT, id TEXT, title TEXT, url TEXT);",
+    )
+}
+
+fn numeric_markets(c: &Connection) -> Result<(), sqlite::Error> {
+    c.execute(
+        "
+        CREATE TABLE scales (platform TEXT, id TEXT, min REAL, max REAL, zero_point REAL,
+            kind TEXT, PRIMARY KEY (platform, id));
+        ALTER TABLE probabilities ADD COLUMN lower REAL;
+        ALTER TABLE probabilities ADD COLUMN upper REAL;",
+    )
+}
+
+fn market_metadata(c: &Connection) -> Result<(), sqlite::Error> {
+    c.execute(
+        "
+        ALTER TABLE probabilities ADD COLUMN volume REAL;
+        ALTER TABLE probabilities ADD COLUMN liquidity REAL;
+        ALTER TABLE details ADD COLUMN volume REAL;
+        ALTER TABLE details ADD COLUMN liquidity REAL;
+        ALTER TABLE details ADD COLUMN bettors INTEGER;
+        ALTER TABLE details ADD COLUMN close_time DATETIME;
+        ALTER TABLE details ADD COLUMN outcome_type TEXT;",
+    )
+}
+
+/// Details used to get a row per observation.
+/// Keep the latest one per market and remember the earlier titles and urls.
+fn unique_details(c: &Connection) -> Result<(), sqlite::Error> {
+    info!("deduplicating details");
+    c.execute(
+        "
+        CREATE TABLE details_history (time DATETIME, platform TEXT, id TEXT, title TEXT, url TEXT);
+        INSERT INTO details_history (time, platform, id, title, url)
+            SELECT COALESCE(
+                (SELECT MIN(p.time) FROM probabilities p WHERE p.platform = d.platform AND p.id = d.id),
+                CURRENT_TIMESTAMP), platform, id, title, url FROM details d
+            GROUP BY platform, id, title, url ORDER BY MIN(rowid);
+        DELETE FROM details WHERE rowid NOT IN (SELECT MAX(rowid) FROM details GROUP BY platform, id);
+        CREATE UNIQUE INDEX idx_details_platform_id ON details(platform, id);
+        CREATE VIEW title_history AS
+            SELECT platform, id, time, title FROM (
+                SELECT platform, id, time, title,
+                LAG(title) OVER (PARTITION BY platform, id ORDER BY rowid) AS previous
+                FROM details_history)
+            WHERE previous IS NULL OR previous != title;",
+    )
+}
+
+fn aggregated_probabilities(c: &Connection) -> Result<(), sqlite::Error> {
+    c.execute(
+        "
+        CREATE TABLE probabilities_hourly (bucket DATETIME, platform TEXT, id TEXT,
+            open REAL, close REAL, min REAL, max REAL, PRIMARY KEY (platform, id, bucket));
+        CREATE TABLE probabilities_daily (bucket DATETIME, platform TEXT, id TEXT,
+            open REAL, close REAL, min REAL, max REAL, PRIMARY KEY (platform, id, bucket));",
+    )
+}
+
+/// Receipts of delivered posts and the probability they told
+fn delivery_receipts(c: &Connection) -> Result<(), sqlite::Error> {
+    c.execute(
+        "
+        ALTER TABLE log ADD COLUMN receipt TEXT;
+        ALTER TABLE log ADD COLUMN prob REAL;",
+    )
 }
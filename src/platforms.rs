@@ -1,19 +1,24 @@
+use crate::http::{HttpClient, Request};
+use crate::pool::bounded_map;
+use chrono::{DateTime, Utc};
 use json::JsonValue;
 use log::*;
 use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Platform {
     Polymarket,
     Metaculus,
     Manifold,
-    _GJOpen,
-    _Kalshi,
+    GJOpen,
+    Kalshi,
     _Augur,
-    _Infer,
+    Infer,
     _Range,
     _Insight,
-    _PredictIt,
+    PredictIt,
     _IEM,
     _HSX,
     _Foresight,
@@ -26,14 +31,77 @@ impl fmt::Display for Platform {
             Platform::Polymarket => write!(f, "Polymarket"),
             Platform::Metaculus => write!(f, "Metaculus"),
             Platform::Manifold => write!(f, "Manifold"),
+            Platform::Kalshi => write!(f, "Kalshi"),
+            Platform::PredictIt => write!(f, "PredictIt"),
+            Platform::GJOpen => write!(f, "GJOpen"),
+            Platform::Infer => write!(f, "INFER"),
             _ => write!(f, "???"),
         }
     }
 }
 
-pub trait PlatformAPI {
+pub trait PlatformAPI: Send + Sync {
     fn id(&self) -> Platform;
-    fn some_markets(&self) -> Vec<MarketStatus>;
+    fn fetch_limit(&self) -> &FetchLimit;
+    /// Most markets the API returns in one page
+    fn page_size(&self) -> i32 {
+        100
+    }
+    /// Fetch one page of at most `limit` markets starting at `cursor`
+    fn fetch_page(&self, cursor: &Cursor, limit: i32) -> Option<Page>;
+    /// Fetch pages until the total limit or the time budget is used up
+    fn some_markets(&self) -> Vec<MarketStatus> {
+        let limit = self.fetch_limit();
+        let mut ret = vec![];
+        let mut fetched = 0;
+        let mut cursor = Cursor::Start;
+        while fetched < limit.total {
+            if Instant::now() >= limit.deadline {
+                warn!("time budget exhausted for {} after {}", self.id(), fetched);
+                break;
+            }
+            let page_limit = self.page_size().min(limit.total - fetched);
+            let page = match self.fetch_page(&cursor, page_limit) {
+                Some(p) => p,
+                None => break, // keep what we got so far
+            };
+            fetched += page.fetched;
+            ret.extend(page.markets);
+            match page.next {
+                Some(next) if page.fetched >= page_limit => cursor = next,
+                _ => break, // last page
+            }
+        }
+        ret
+    }
+}
+
+/// How much to fetch from one platform in one run
+#[derive(Debug, Clone, Copy)]
+pub struct FetchLimit {
+    /// Markets to request over all pages
+    pub total: i32,
+    /// No more pages are requested after this
+    pub deadline: Instant,
+}
+
+/// Where the next page starts
+#[derive(Debug, PartialEq)]
+pub enum Cursor {
+    Start,
+    /// Manifold lists markets before some market id
+    Before(String),
+    /// Number of markets to skip
+    Offset(i32),
+    /// Whatever the API returned to continue, Metaculus gives an URL
+    Next(String),
+}
+
+pub struct Page {
+    pub markets: Vec<MarketStatus>,
+    /// Number of raw markets in the response, before any filtering
+    pub fetched: i32,
+    pub next: Option<Cursor>,
 }
 
 #[derive(Debug)]
@@ -43,121 +111,375 @@ pub struct MarketStatus {
     pub prob: f32,
     pub url: String,
     pub title: String,
+    /// Numeric markets store their position within the scale as prob
+    pub scale: Option<Scale>,
+    /// Interquartile range on the same scale as prob
+    pub interval: Option<(f32, f32)>,
+    pub meta: Metadata,
 }
 
-pub struct Manifold {
-    fetch_limit: i32,
+/// What else a platform tells about a market, where available.
+/// Volume and liquidity are in the platform currency, e.g. mana for Manifold.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    pub volume: Option<f64>,
+    pub liquidity: Option<f64>,
+    pub bettors: Option<i64>,
+    pub close_time: Option<DateTime<Utc>>,
+    pub outcome_type: Option<String>,
 }
 
-impl Manifold {
-    pub fn new_boxed(fetch_limit: i32) -> Box<dyn PlatformAPI> {
-        Box::new(Manifold { fetch_limit })
+/// Range of a numeric market.
+/// The expected value is stored as its relative position between min and max,
+/// so a shift is judged relative to the range like a probability change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scale {
+    pub kind: ScaleKind,
+    pub min: f64,
+    pub max: f64,
+    /// Log scales are exponential around this point, Manifold uses -1
+    pub zero_point: Option<f64>,
+}
+
+/// Which statistic a numeric market reports
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScaleKind {
+    Expected,
+    Median,
+    /// Median of a date as unix timestamp
+    Date,
+}
+
+impl fmt::Display for ScaleKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScaleKind::Expected => write!(f, "expected"),
+            ScaleKind::Median => write!(f, "median"),
+            ScaleKind::Date => write!(f, "date"),
+        }
     }
 }
 
-impl PlatformAPI for Manifold {
-    fn id(self: &Self) -> Platform {
-        Platform::Manifold
+impl std::str::FromStr for ScaleKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "expected" => Ok(ScaleKind::Expected),
+            "median" => Ok(ScaleKind::Median),
+            "date" => Ok(ScaleKind::Date),
+            _ => Err(format!("unknown scale kind {}", s)),
+        }
     }
-    fn some_markets(&self) -> Vec<MarketStatus> {
-        let url = format!(
-            "https://api.manifold.markets/v0/search-markets?limit={}&sort=last-updated&term=",
-            self.fetch_limit
-        );
-        let call = ureq::get(url.as_str()).call();
-        let response = match call {
-            Ok(c) => c.into_string().expect("body"),
-            Err(e) => {
-                warn!("{:?}", e);
-                return vec![];
+}
+
+impl Scale {
+    /// Relative position of a value, 0.0 at min and 1.0 at max
+    pub fn normalize(&self, value: f64) -> f32 {
+        let x = match self.zero_point {
+            Some(zp) => {
+                let d = (self.max - zp) / (self.min - zp);
+                ((value - zp) / (self.min - zp)).ln() / d.ln()
             }
+            None => (value - self.min) / (self.max - self.min),
         };
+        x as f32
+    }
+
+    /// Inverse of normalize
+    pub fn value(&self, x: f32) -> f64 {
+        let x = x as f64;
+        match self.zero_point {
+            Some(zp) => {
+                let d = (self.max - zp) / (self.min - zp);
+                zp + (self.min - zp) * d.powf(x)
+            }
+            None => self.min + x * (self.max - self.min),
+        }
+    }
+}
+
+pub struct Manifold {
+    http: Arc<dyn HttpClient>,
+    fetch_limit: FetchLimit,
+    /// Threads fetching market details, on top of the platform workers
+    workers: usize,
+}
+
+impl Manifold {
+    pub fn new_boxed(
+        http: Arc<dyn HttpClient>,
+        fetch_limit: FetchLimit,
+        workers: usize,
+    ) -> Box<dyn PlatformAPI> {
+        Box::new(Manifold {
+            http,
+            fetch_limit,
+            workers,
+        })
+    }
+
+    /// Turn one market of the list into statuses, fetching details if needed.
+    fn parse_market(&self, o: &JsonValue) -> Vec<MarketStatus> {
         let mut ret = vec![];
-        if let Ok(j) = json::parse(response.as_str()) {
-            for o in j.members() {
-                let bettors = o["uniqueBettorCount"].as_i32().expect("bettor count");
-                if 55 > bettors {
-                    continue; // not enough bettors
-                }
-                let volume = o["volume"].as_f32().expect("volume");
-                if 500.0 > volume {
-                    continue; // not enough volume
-                }
-                let title = o["question"].to_string();
-                if !allowed_title(&title) {
-                    continue;
-                }
-                info!("Allowed: {}", title);
-                let id = o["id"].to_string();
-                let url = format!("{}?r=bWFya3R3c2U", o["url"]);
-                let outcome_type = o["outcomeType"].as_str().expect("outcome type");
-                match outcome_type {
-                    "BINARY" => {
-                        let prob = o["probability"].as_f32().unwrap_or(-1.0);
+        let bettors = o["uniqueBettorCount"].as_i32().expect("bettor count");
+        if 55 > bettors {
+            return vec![]; // not enough bettors
+        }
+        let volume = o["volume"].as_f32().expect("volume");
+        if 500.0 > volume {
+            return vec![]; // not enough volume
+        }
+        let title = o["question"].to_string();
+        if !allowed_title(&title) {
+            return vec![];
+        }
+        info!("Allowed: {}", title);
+        let id = o["id"].to_string();
+        let url = format!("{}?r=bWFya3R3c2U", o["url"]);
+        let outcome_type = o["outcomeType"].as_str().expect("outcome type");
+        let meta = manifold_metadata(o);
+        match outcome_type {
+            "BINARY" => {
+                let prob = o["probability"].as_f32().unwrap_or(-1.0);
+                let status = MarketStatus {
+                    platform: Platform::Manifold,
+                    id,
+                    prob,
+                    url,
+                    title,
+                    scale: None,
+                    interval: None,
+                    meta: meta.clone(),
+                };
+                ret.push(status);
+            }
+            "MULTIPLE_CHOICE" | "FREE_RESPONSE" => {
+                let api_url = format!("https://api.manifold.markets/v0/market/{}", id);
+                let call = self.http.send(&Request::get(api_url.as_str()));
+                let response = match call {
+                    Ok(r) => r.body,
+                    Err(e) => {
+                        warn!("{}", e);
+                        return vec![];
+                    }
+                };
+                if let Ok(d) = json::parse(response.as_str()) {
+                    let members = d["answers"].members();
+                    let count = members.clone().count();
+                    if 100.0 > (volume / count as f32) {
+                        return vec![]; //not enough average volume
+                    }
+                    for a in members {
+                        let a_title = a["text"].to_string();
+                        let a_id = if a.has_key("index") {
+                            a["index"].as_f32().expect("index") as i32
+                        } else if a.has_key("number") {
+                            a["number"].as_f32().expect("number") as i32
+                        } else {
+                            error!("answer without index nor number: {:#?}", a);
+                            -1
+                        };
+                        let prob = a["probability"].as_f32().unwrap_or(-1.0);
                         let status = MarketStatus {
                             platform: Platform::Manifold,
-                            id,
+                            id: format!("{} {}", id, a_id),
                             prob,
-                            url,
-                            title,
+                            url: url.clone(),
+                            title: format!("{} {}", title, a_title),
+                            scale: None,
+                            interval: None,
+                            meta: meta.clone(),
                         };
                         ret.push(status);
                     }
-                    "MULTIPLE_CHOICE" | "FREE_RESPONSE" => {
-                        let api_url = format!("https://api.manifold.markets/v0/market/{}", id);
-                        let call = ureq::get(api_url.as_str()).call();
-                        let response = match call {
-                            Ok(c) => c.into_string().expect("body"),
-                            Err(e) => {
-                                warn!("{:?}", e);
-                                continue;
-                            }
-                        };
-                        if let Ok(d) = json::parse(response.as_str()) {
-                            let members = d["answers"].members();
-                            let count = members.clone().count();
-                            if 100.0 > (volume / count as f32) {
-                                continue; //not enough average volume
-                            }
-                            for a in members {
-                                let a_title = a["text"].to_string();
-                                let a_id = if a.has_key("index") {
-                                    a["index"].as_f32().expect("index") as i32
-                                } else if a.has_key("number") {
-                                    a["number"].as_f32().expect("number") as i32
-                                } else {
-                                    error!("answer without index nor number: {:#?}", a);
-                                    -1
-                                };
-                                let prob = a["probability"].as_f32().unwrap_or(-1.0);
-                                let status = MarketStatus {
-                                    platform: Platform::Manifold,
-                                    id: format!("{} {}", id, a_id),
-                                    prob,
-                                    url: url.clone(),
-                                    title: format!("{} {}", title, a_title),
-                                };
-                                ret.push(status);
-                            }
-                        }
-                    }
-                    "STONK" => {
-                        // ignore
+                }
+            }
+            "PSEUDO_NUMERIC" => {
+                if let Some(status) = parse_manifold_numeric(o, id, url, title) {
+                    ret.push(status);
+                }
+            }
+            "NUMBER" => {
+                let api_url = format!("https://api.manifold.markets/v0/market/{}", id);
+                let call = self.http.send(&Request::get(api_url.as_str()));
+                let response = match call {
+                    Ok(r) => r.body,
+                    Err(e) => {
+                        warn!("{}", e);
+                        return vec![];
                     }
-                    _ => {
-                        warn!("Unhandled outcome type {}", outcome_type);
-                        continue;
+                };
+                if let Ok(d) = json::parse(response.as_str()) {
+                    if let Some(status) = parse_manifold_number(&d, id, url, title) {
+                        ret.push(status);
                     }
                 }
             }
+            "STONK" => {
+                // ignore
+            }
+            _ => {
+                warn!("Unhandled outcome type {}", outcome_type);
+            }
+        }
+        ret
+    }
+}
+
+impl PlatformAPI for Manifold {
+    fn id(&self) -> Platform {
+        Platform::Manifold
+    }
+    fn fetch_limit(&self) -> &FetchLimit {
+        &self.fetch_limit
+    }
+    fn page_size(&self) -> i32 {
+        1000
+    }
+    fn fetch_page(&self, cursor: &Cursor, limit: i32) -> Option<Page> {
+        let mut url = format!(
+            "https://api.manifold.markets/v0/markets?limit={}&sort=updated-time&order=desc",
+            limit
+        );
+        if let Cursor::Before(id) = cursor {
+            url = format!("{}&before={}", url, id);
+        }
+        let call = self.http.send(&Request::get(url.as_str()));
+        let response = match call {
+            Ok(r) => r.body,
+            Err(e) => {
+                warn!("{}", e);
+                return None;
+            }
+        };
+        let mut ret = vec![];
+        let mut fetched = 0;
+        let mut next = None;
+        if let Ok(j) = json::parse(response.as_str()) {
+            fetched = j.len() as i32;
+            next = j
+                .members()
+                .last()
+                .map(|o| Cursor::Before(o["id"].to_string()));
+            let candidates: Vec<&JsonValue> = j.members().collect();
+            ret = bounded_map(candidates, self.workers, |o| self.parse_market(o))
+                .into_iter()
+                .flatten()
+                .collect();
         } else {
             dbg!(response);
         };
-        ret
+        Some(Page {
+            markets: ret,
+            fetched,
+            next,
+        })
+    }
+}
+
+fn parse_manifold_numeric(
+    o: &JsonValue,
+    id: String,
+    url: String,
+    title: String,
+) -> Option<MarketStatus> {
+    let min = o["min"].as_f64()?;
+    let max = o["max"].as_f64()?;
+    let value = o["value"].as_f64()?;
+    let meta = manifold_metadata(o);
+    // Manifold maps log scales as (max - min + 1)^p + min - 1
+    let zero_point = match o["isLogScale"].as_bool() {
+        Some(true) => Some(min - 1.0),
+        _ => None,
+    };
+    let scale = Scale {
+        kind: ScaleKind::Expected,
+        min,
+        max,
+        zero_point,
+    };
+    Some(MarketStatus {
+        platform: Platform::Manifold,
+        id,
+        prob: scale.normalize(value),
+        url,
+        title,
+        scale: Some(scale),
+        interval: None,
+        meta,
+    })
+}
+
+/// Numeric markets are made of range buckets like "10-20",
+/// the expected value weighs each midpoint by its probability.
+fn parse_manifold_number(
+    d: &JsonValue,
+    id: String,
+    url: String,
+    title: String,
+) -> Option<MarketStatus> {
+    let min = d["min"].as_f64()?;
+    let max = d["max"].as_f64()?;
+    let mut value = 0.0;
+    let mut total = 0.0;
+    for a in d["answers"].members() {
+        let p = a["probability"].as_f64()?;
+        let midpoint = match a["midpoint"].as_f64() {
+            Some(m) => m,
+            None => bucket_midpoint(a["text"].as_str()?)?,
+        };
+        value += p * midpoint;
+        total += p;
+    }
+    if total <= 0.0 {
+        return None;
+    }
+    let meta = manifold_metadata(d);
+    let scale = Scale {
+        kind: ScaleKind::Expected,
+        min,
+        max,
+        zero_point: None,
+    };
+    Some(MarketStatus {
+        platform: Platform::Manifold,
+        id,
+        prob: scale.normalize(value / total),
+        url,
+        title,
+        scale: Some(scale),
+        interval: None,
+        meta,
+    })
+}
+
+fn manifold_metadata(o: &JsonValue) -> Metadata {
+    Metadata {
+        volume: o["volume"].as_f64(),
+        liquidity: o["totalLiquidity"].as_f64(),
+        bettors: o["uniqueBettorCount"].as_i64(),
+        close_time: o["closeTime"]
+            .as_i64()
+            .and_then(DateTime::from_timestamp_millis),
+        outcome_type: o["outcomeType"].as_str().map(String::from),
     }
 }
 
-fn allowed_title(title: &String) -> bool {
+/// Timestamps like "2026-07-01T07:01:00.000Z"
+fn parse_time(v: &JsonValue) -> Option<DateTime<Utc>> {
+    let t = DateTime::parse_from_rfc3339(v.as_str()?).ok()?;
+    Some(t.with_timezone(&Utc))
+}
+
+fn bucket_midpoint(text: &str) -> Option<f64> {
+    // skip the first char, so a leading minus sign is not a separator
+    let split = text.char_indices().skip(1).find(|(_, c)| *c == '-')?.0;
+    let low = text[..split].trim().parse::<f64>().ok()?;
+    let high = text[split + 1..].trim().parse::<f64>().ok()?;
+    Some((low + high) / 2.0)
+}
+
+fn allowed_title(title: &str) -> bool {
     let lowercase_title = title.to_lowercase();
     for x in [
         "[add responses]",
@@ -187,13 +509,19 @@ fn allowed_title(title: &String) -> bool {
 }
 
 pub struct Metaculus {
-    fetch_limit: i32,
+    http: Arc<dyn HttpClient>,
+    fetch_limit: FetchLimit,
     access_token: String,
 }
 
 impl Metaculus {
-    pub fn new_boxed(fetch_limit: i32, access_token: String) -> Box<dyn PlatformAPI> {
+    pub fn new_boxed(
+        http: Arc<dyn HttpClient>,
+        fetch_limit: FetchLimit,
+        access_token: String,
+    ) -> Box<dyn PlatformAPI> {
         Box::new(Metaculus {
+            http,
             fetch_limit,
             access_token,
         })
@@ -201,103 +529,239 @@ impl Metaculus {
 }
 
 impl PlatformAPI for Metaculus {
-    fn id(self: &Self) -> Platform {
+    fn id(&self) -> Platform {
         Platform::Metaculus
     }
-    fn some_markets(&self) -> Vec<MarketStatus> {
-        let url = format!("https://www.metaculus.com/api/posts/?forecast_type=binary&limit={}&order_by=user_last_forecasts_date&statuses=open", self.fetch_limit);
-        let call = ureq::get(url.as_str())
-            .set("Authorization", self.access_token.as_str())
-            .call();
+    fn fetch_limit(&self) -> &FetchLimit {
+        &self.fetch_limit
+    }
+    fn fetch_page(&self, cursor: &Cursor, limit: i32) -> Option<Page> {
+        let url = match cursor {
+            Cursor::Next(next) => with_limit(next, limit),
+            _ => format!("https://www.metaculus.com/api/posts/?forecast_type=binary,multiple_choice,numeric,date&limit={}&order_by=user_last_forecasts_date&statuses=open", limit),
+        };
+        let call = self
+            .http
+            .send(&Request::get(url.as_str()).header("Authorization", self.access_token.as_str()));
         let response = match call {
-            Ok(c) => c.into_string().expect("body"),
+            Ok(r) => r.body,
             Err(e) => {
-                warn!("{:?}", e);
-                return vec![];
+                warn!("{}", e);
+                return None;
             }
         };
         let mut ret = vec![];
+        let mut fetched = 0;
+        let mut next = None;
         if let Ok(j) = json::parse(response.as_str()) {
+            fetched = j["results"].len() as i32;
+            next = j["next"].as_str().map(|n| Cursor::Next(n.to_string()));
             for o in j["results"].members() {
-                println!("member: {}", o);
-                let _question = o["title"].clone();
-                if 30 > o["nr_forecasters"].as_i32().expect("num casters") {
-                    continue; // not enough forecasters
-                };
-                let prob = o["community_prediction"]["full"]["q2"]
-                    .as_f32()
-                    .unwrap_or(-1.0);
-                let id = o["id"].to_string();
-                let url = format!("https://www.metaculus.com/questions/{}", id);
-                let title = o["title"].to_string();
-                let status = MarketStatus {
-                    platform: self.id(),
-                    id,
-                    prob,
-                    url,
-                    title,
-                };
-                ret.push(status);
+                debug!("member: {}", o);
+                ret.append(&mut parse_metaculus(o));
             }
         } else {
             dbg!(response);
         };
-        ret
+        Some(Page {
+            markets: ret,
+            fetched,
+            next,
+        })
     }
 }
 
+/// The next URL keeps the limit of the first page, but later pages may need less
+fn with_limit(url: &str, limit: i32) -> String {
+    let (base, query) = url.split_once('?').unwrap_or((url, ""));
+    let mut params: Vec<String> = query
+        .split('&')
+        .filter(|p| !p.is_empty() && !p.starts_with("limit="))
+        .map(str::to_string)
+        .collect();
+    params.insert(0, format!("limit={}", limit));
+    format!("{}?{}", base, params.join("&"))
+}
+
+fn parse_metaculus(o: &JsonValue) -> Vec<MarketStatus> {
+    let mut ret = vec![];
+    if 30 > o["nr_forecasters"].as_i32().unwrap_or(0) {
+        return ret; // not enough forecasters
+    };
+    let q = &o["question"];
+    let latest = &q["aggregations"]["recency_weighted"]["latest"];
+    if latest.is_null() {
+        return ret; // no community prediction yet
+    }
+    let id = o["id"].to_string();
+    let url = format!("https://www.metaculus.com/questions/{}", id);
+    let title = o["title"].to_string();
+    let question_type = q["type"].as_str().unwrap_or("");
+    let meta = Metadata {
+        bettors: o["nr_forecasters"].as_i64(),
+        close_time: parse_time(&q["scheduled_close_time"]),
+        outcome_type: Some(question_type.to_string()),
+        ..Default::default()
+    };
+    match question_type {
+        "binary" => {
+            let prob = latest["centers"][0].as_f32().unwrap_or(-1.0);
+            ret.push(MarketStatus {
+                platform: Platform::Metaculus,
+                id,
+                prob,
+                url,
+                title,
+                scale: None,
+                interval: None,
+                meta: meta.clone(),
+            });
+        }
+        "multiple_choice" => {
+            // options and forecast values share the same order
+            for (i, option) in q["options"].members().enumerate() {
+                let prob = latest["forecast_values"][i].as_f32().unwrap_or(-1.0);
+                ret.push(MarketStatus {
+                    platform: Platform::Metaculus,
+                    id: format!("{} {}", id, i),
+                    prob,
+                    url: url.clone(),
+                    title: format!("{} {}", title, option),
+                    scale: None,
+                    interval: None,
+                    meta: meta.clone(),
+                });
+            }
+        }
+        "numeric" | "date" => {
+            let scaling = &q["scaling"];
+            let (min, max) = match (scaling["range_min"].as_f64(), scaling["range_max"].as_f64()) {
+                (Some(min), Some(max)) => (min, max),
+                _ => return ret,
+            };
+            let kind = if question_type == "date" {
+                ScaleKind::Date
+            } else {
+                ScaleKind::Median
+            };
+            // centers are already relative positions within the range
+            let prob = latest["centers"][0].as_f32().unwrap_or(-1.0);
+            let interval = match (
+                latest["interval_lower_bounds"][0].as_f32(),
+                latest["interval_upper_bounds"][0].as_f32(),
+            ) {
+                (Some(lower), Some(upper)) => Some((lower, upper)),
+                _ => None,
+            };
+            ret.push(MarketStatus {
+                platform: Platform::Metaculus,
+                id,
+                prob,
+                url,
+                title,
+                scale: Some(Scale {
+                    kind,
+                    min,
+                    max,
+                    zero_point: scaling["zero_point"].as_f64(),
+                }),
+                interval,
+                meta: meta.clone(),
+            });
+        }
+        _ => {
+            warn!("Unhandled question type {}", question_type);
+        }
+    }
+    ret
+}
+
 pub struct Polymarket {
-    fetch_limit: i32,
+    http: Arc<dyn HttpClient>,
+    fetch_limit: FetchLimit,
 }
 
 impl Polymarket {
-    pub fn new_boxed(fetch_limit: i32) -> Box<dyn PlatformAPI> {
-        Box::new(Polymarket { fetch_limit })
+    pub fn new_boxed(http: Arc<dyn HttpClient>, fetch_limit: FetchLimit) -> Box<dyn PlatformAPI> {
+        Box::new(Polymarket { http, fetch_limit })
     }
 }
 
 impl PlatformAPI for Polymarket {
-    fn id(self: &Self) -> Platform {
+    fn id(&self) -> Platform {
         Platform::Polymarket
     }
-    fn some_markets(&self) -> Vec<MarketStatus> {
+    fn fetch_limit(&self) -> &FetchLimit {
+        &self.fetch_limit
+    }
+    fn fetch_page(&self, cursor: &Cursor, limit: i32) -> Option<Page> {
         let mut ret = vec![];
-        let query = format!(
-            r#"{{ markets(limit: {}, order: "updated_at DESC")
-                       {{ question, outcomePrices, slug, volume24hr, liquidity, updatedAt, events {{ slug }} }} }}"#,
-            self.fetch_limit
-        );
-        let json_query = format!(
-            r#"{{"query": "{}"}}"#,
-            query.replace(r#"""#, r#"\""#).replace("\n", "")
+        let offset = match cursor {
+            Cursor::Offset(o) => *o,
+            _ => 0,
+        };
+        let url = format!(
+            "https://gamma-api.polymarket.com/events?limit={}&offset={}&active=true&closed=false&order=updatedAt&ascending=false",
+            limit, offset
         );
-        let graphql_endpoint = "https://gamma-api.polymarket.com/query";
-        let call = ureq::post(graphql_endpoint)
-            .set("Content-Type", "application/json")
-            .send_string(json_query.as_str());
+        let call = self
+            .http
+            .send(&Request::get(url.as_str()).header("Accept", "application/json"));
         let response = match call {
-            Ok(c) => c.into_string().expect("body"),
+            Ok(r) => r.body,
             Err(e) => {
-                warn!("{:?}", e);
-                return vec![];
+                warn!("{}", e);
+                return None;
             }
         };
+        let mut fetched = 0;
         if let Ok(j) = json::parse(response.as_str()) {
-            for o in j["data"]["markets"].members() {
-                if let Some(status) = parse_polymarket(o) {
-                    ret.push(status);
-                } else {
-                    debug!("Polymarket drop: {:?}", o);
-                }
+            fetched = j.len() as i32;
+            for e in j.members() {
+                ret.append(&mut parse_polymarket_event(e));
             }
         } else {
             dbg!(response);
         };
-        ret
+        Some(Page {
+            markets: ret,
+            fetched,
+            next: Some(Cursor::Offset(offset + fetched)),
+        })
     }
 }
 
-fn parse_polymarket(o: &JsonValue) -> Option<MarketStatus> {
+/// An event with several child markets is a market group.
+/// Each child gets the event slug as id prefix,
+/// so publication dedup treats the whole event as one unit.
+fn parse_polymarket_event(e: &JsonValue) -> Vec<MarketStatus> {
+    let mut ret = vec![];
+    let e_slug = e["slug"].to_string();
+    let open_markets: Vec<&JsonValue> = e["markets"]
+        .members()
+        .filter(|o| o["active"] == true && o["closed"] != true)
+        .collect();
+    let grouped = open_markets.len() > 1;
+    for o in open_markets {
+        let mut status = match parse_polymarket(o, &e_slug) {
+            Some(s) => s,
+            None => {
+                debug!("Polymarket drop: {:?}", o);
+                continue;
+            }
+        };
+        if grouped {
+            status.id = format!("{} {}", e_slug, status.id);
+            if let Some(item) = o["groupItemTitle"].as_str() {
+                status.title = format!("{} {}", e["title"], item);
+            }
+        }
+        ret.push(status);
+    }
+    ret
+}
+
+fn parse_polymarket(o: &JsonValue, event_slug: &str) -> Option<MarketStatus> {
     let volume24hr = o["volume24hr"].as_f32()?;
     let liquidity = o["liquidity"].to_string().parse::<f32>().ok()?;
     if liquidity < 500.0 || volume24hr < 10.0 {
@@ -306,21 +770,650 @@ fn parse_polymarket(o: &JsonValue) -> Option<MarketStatus> {
     let prices = json::parse(o["outcomePrices"].as_str()?).ok()?;
     let prob = prices[0].to_string().parse::<f32>().ok()?;
     let id = o["slug"].to_string();
-    let mut url: String = "broken".to_string();
     if o["question"].is_null() {
         return Option::None;
     }
-    for e in o["events"].members() {
-        let e_slug = e["slug"].to_string();
-        url = format!("https://polymarket.com/event/{}/{}", e_slug, id);
+    let url = format!("https://polymarket.com/event/{}/{}", event_slug, id);
+    let mut title = o["question"].to_string();
+    // the price is for the first outcome, which is not always "Yes"
+    let outcomes = json::parse(o["outcomes"].as_str().unwrap_or("[]")).ok()?;
+    if let Some(first) = outcomes[0].as_str() {
+        if first != "Yes" {
+            title = format!("{} {}", title, first);
+        }
     }
-    let title = o["question"].to_string();
     let platform = Platform::Polymarket;
+    let meta = Metadata {
+        volume: o["volumeNum"].as_f64(),
+        liquidity: Some(liquidity as f64),
+        close_time: parse_time(&o["endDate"]),
+        outcome_type: Some("binary".to_string()),
+        ..Default::default()
+    };
     Some(MarketStatus {
         platform,
         id,
         prob,
         url,
         title,
+        scale: None,
+        interval: None,
+        meta,
+    })
+}
+
+pub struct Kalshi {
+    http: Arc<dyn HttpClient>,
+    fetch_limit: FetchLimit,
+}
+
+impl Kalshi {
+    pub fn new_boxed(http: Arc<dyn HttpClient>, fetch_limit: FetchLimit) -> Box<dyn PlatformAPI> {
+        Box::new(Kalshi { http, fetch_limit })
+    }
+}
+
+impl PlatformAPI for Kalshi {
+    fn id(&self) -> Platform {
+        Platform::Kalshi
+    }
+    fn fetch_limit(&self) -> &FetchLimit {
+        &self.fetch_limit
+    }
+    fn page_size(&self) -> i32 {
+        1000
+    }
+    fn fetch_page(&self, cursor: &Cursor, limit: i32) -> Option<Page> {
+        let mut url = format!(
+            "https://api.elections.kalshi.com/trade-api/v2/markets?limit={}&status=open",
+            limit
+        );
+        if let Cursor::Next(c) = cursor {
+            url = format!("{}&cursor={}", url, c);
+        }
+        let call = self
+            .http
+            .send(&Request::get(url.as_str()).header("Accept", "application/json"));
+        let response = match call {
+            Ok(r) => r.body,
+            Err(e) => {
+                warn!("{}", e);
+                return None;
+            }
+        };
+        let mut ret = vec![];
+        let mut fetched = 0;
+        let mut next = None;
+        if let Ok(j) = json::parse(response.as_str()) {
+            fetched = j["markets"].len() as i32;
+            next = j["cursor"]
+                .as_str()
+                .filter(|c| !c.is_empty())
+                .map(|c| Cursor::Next(c.to_string()));
+            for o in j["markets"].members() {
+                if let Some(status) = parse_kalshi(o) {
+                    ret.push(status);
+                } else {
+                    debug!("Kalshi drop: {}", o["ticker"]);
+                }
+            }
+        } else {
+            warn!("unparsable Kalshi response");
+        };
+        Some(Page {
+            markets: ret,
+            fetched,
+            next,
+        })
+    }
+}
+
+fn parse_kalshi(o: &JsonValue) -> Option<MarketStatus> {
+    let volume = o["volume"].as_i64()?;
+    let open_interest = o["open_interest"].as_i64()?;
+    if volume < 1000 || open_interest < 500 {
+        return None;
+    };
+    // prices are in cents, without quotes on both sides there is no midpoint
+    let bid = o["yes_bid"].as_f32()?;
+    let ask = o["yes_ask"].as_f32()?;
+    if bid <= 0.0 || ask <= 0.0 || ask < bid {
+        return None;
+    }
+    let prob = (bid + ask) / 200.0;
+    let ticker = o["ticker"].as_str()?;
+    let event_ticker = o["event_ticker"].as_str()?;
+    let title = o["title"].as_str()?;
+    if !allowed_title(title) {
+        return None;
+    }
+    // multi-market events get a postfix for each outcome like Manifold answers
+    let (id, title) = match ticker.strip_prefix(event_ticker) {
+        Some(outcome) if !outcome.is_empty() => (
+            format!("{} {}", event_ticker, outcome.trim_start_matches('-')),
+            format!("{} {}", title, o["yes_sub_title"]),
+        ),
+        _ => (ticker.to_string(), title.to_string()),
+    };
+    let series = event_ticker.split('-').next()?.to_lowercase();
+    let url = format!("https://kalshi.com/markets/{}", series);
+    // liquidity is in cents, volume in contracts worth up to a dollar
+    let meta = Metadata {
+        volume: Some(volume as f64),
+        liquidity: o["liquidity"].as_f64().map(|l| l / 100.0),
+        bettors: None,
+        close_time: parse_time(&o["close_time"]),
+        outcome_type: o["market_type"].as_str().map(String::from),
+    };
+    Some(MarketStatus {
+        platform: Platform::Kalshi,
+        id,
+        prob,
+        url,
+        title,
+        scale: None,
+        interval: None,
+        meta,
     })
 }
+
+pub struct PredictIt {
+    http: Arc<dyn HttpClient>,
+    fetch_limit: FetchLimit,
+}
+
+impl PredictIt {
+    pub fn new_boxed(http: Arc<dyn HttpClient>, fetch_limit: FetchLimit) -> Box<dyn PlatformAPI> {
+        Box::new(PredictIt { http, fetch_limit })
+    }
+}
+
+impl PlatformAPI for PredictIt {
+    fn id(&self) -> Platform {
+        Platform::PredictIt
+    }
+    fn fetch_limit(&self) -> &FetchLimit {
+        &self.fetch_limit
+    }
+    fn page_size(&self) -> i32 {
+        i32::MAX
+    }
+    fn fetch_page(&self, _cursor: &Cursor, limit: i32) -> Option<Page> {
+        // the feed has no paging or limit, it always returns all markets
+        let url = "https://www.predictit.org/api/marketdata/all/";
+        let call = self
+            .http
+            .send(&Request::get(url).header("Accept", "application/json"));
+        let response = match call {
+            Ok(r) => r.body,
+            Err(e) => {
+                warn!("{}", e);
+                return None;
+            }
+        };
+        let mut ret = vec![];
+        let mut fetched = 0;
+        if let Ok(j) = json::parse(response.as_str()) {
+            for o in j["markets"].members().take(limit as usize) {
+                fetched += 1;
+                ret.append(&mut parse_predictit(o));
+            }
+        } else {
+            warn!("unparsable PredictIt response");
+        };
+        Some(Page {
+            markets: ret,
+            fetched,
+            next: None,
+        })
+    }
+}
+
+fn parse_predictit(o: &JsonValue) -> Vec<MarketStatus> {
+    let mut ret = vec![];
+    if o["status"] != "Open" {
+        return ret;
+    }
+    let title = o["name"].to_string();
+    if !allowed_title(&title) {
+        return ret;
+    }
+    let id = o["id"].to_string();
+    let url = o["url"].to_string();
+    let single = o["contracts"].len() == 1;
+    for c in o["contracts"].members() {
+        if c["status"] != "Open" {
+            continue;
+        }
+        let prob = match c["lastTradePrice"].as_f32() {
+            Some(p) => p,
+            None => continue, // never traded
+        };
+        let meta = Metadata {
+            close_time: parse_time(&c["dateEnd"]),
+            outcome_type: Some("contract".to_string()),
+            ..Default::default()
+        };
+        // the only contract of a market just repeats the market name
+        let c_title = if single {
+            title.clone()
+        } else {
+            format!("{} {}", title, c["name"])
+        };
+        ret.push(MarketStatus {
+            platform: Platform::PredictIt,
+            id: format!("{} {}", id, c["id"]),
+            prob,
+            url: url.clone(),
+            title: c_title,
+            scale: None,
+            interval: None,
+            meta: meta.clone(),
+        });
+    }
+    ret
+}
+
+/// Good Judgment Open and INFER both run on Cultivate Labs
+/// and share the same question API.
+pub struct Cultivate {
+    platform: Platform,
+    http: Arc<dyn HttpClient>,
+    fetch_limit: FetchLimit,
+    access_token: String,
+}
+
+impl Cultivate {
+    pub fn new_boxed(
+        http: Arc<dyn HttpClient>,
+        platform: Platform,
+        fetch_limit: FetchLimit,
+        access_token: String,
+    ) -> Box<dyn PlatformAPI> {
+        Box::new(Cultivate {
+            http,
+            platform,
+            fetch_limit,
+            access_token,
+        })
+    }
+
+    fn base_url(&self) -> &'static str {
+        cultivate_base_url(&self.platform)
+    }
+}
+
+fn cultivate_base_url(platform: &Platform) -> &'static str {
+    match platform {
+        Platform::Infer => "https://www.randforecastinginitiative.org",
+        _ => "https://www.gjopen.com",
+    }
+}
+
+impl PlatformAPI for Cultivate {
+    fn id(&self) -> Platform {
+        self.platform
+    }
+    fn fetch_limit(&self) -> &FetchLimit {
+        &self.fetch_limit
+    }
+    fn fetch_page(&self, cursor: &Cursor, limit: i32) -> Option<Page> {
+        let offset = match cursor {
+            Cursor::Offset(o) => *o,
+            _ => 0,
+        };
+        let url = format!(
+            "{}/api/v1/questions?status=active&limit={}&offset={}",
+            self.base_url(),
+            limit,
+            offset
+        );
+        let call = self.http.send(
+            &Request::get(url.as_str())
+                .header("Accept", "application/json")
+                .header(
+                    "Authorization",
+                    format!("Bearer {}", self.access_token).as_str(),
+                ),
+        );
+        let response = match call {
+            Ok(r) => r.body,
+            Err(e) => {
+                warn!("{}", e);
+                return None;
+            }
+        };
+        let mut ret = vec![];
+        let mut fetched = 0;
+        if let Ok(j) = json::parse(response.as_str()) {
+            fetched = j["questions"].len() as i32;
+            for o in j["questions"].members() {
+                ret.append(&mut parse_cultivate(self.id(), o));
+            }
+        } else {
+            warn!("unparsable {} response", self.platform);
+        };
+        Some(Page {
+            markets: ret,
+            fetched,
+            next: Some(Cursor::Offset(offset + fetched)),
+        })
+    }
+}
+
+fn parse_cultivate(platform: Platform, o: &JsonValue) -> Vec<MarketStatus> {
+    let mut ret = vec![];
+    if 30 > o["predictors_count"].as_i32().unwrap_or(0) {
+        return ret; // not enough forecasters
+    }
+    let title = o["name"].to_string();
+    if !allowed_title(&title) {
+        return ret;
+    }
+    let id = o["id"].to_string();
+    let url = format!("{}/questions/{}", cultivate_base_url(&platform), id);
+    let meta = Metadata {
+        bettors: o["predictors_count"].as_i64(),
+        close_time: parse_time(&o["ends_at"]),
+        outcome_type: o["type"].as_str().map(String::from),
+        ..Default::default()
+    };
+    let answers = &o["answers"];
+    let yes = answers.members().find(|a| a["name"] == "Yes");
+    if let (2, Some(yes)) = (answers.len(), yes) {
+        let prob = yes["probability"].as_f32().unwrap_or(-1.0);
+        ret.push(MarketStatus {
+            platform,
+            id,
+            prob,
+            url,
+            title,
+            scale: None,
+            interval: None,
+            meta: meta.clone(),
+        });
+        return ret;
+    }
+    for a in answers.members() {
+        let prob = a["probability"].as_f32().unwrap_or(-1.0);
+        ret.push(MarketStatus {
+            platform,
+            id: format!("{} {}", id, a["id"]),
+            prob,
+            url: url.clone(),
+            title: format!("{} {}", title, a["name"]),
+            scale: None,
+            interval: None,
+            meta: meta.clone(),
+        });
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Serves pages of two markets each, forever
+    struct Endless {
+        fetch_limit: FetchLimit,
+    }
+
+    impl PlatformAPI for Endless {
+        fn id(&self) -> Platform {
+            Platform::Manifold
+        }
+        fn fetch_limit(&self) -> &FetchLimit {
+            &self.fetch_limit
+        }
+        fn page_size(&self) -> i32 {
+            2
+        }
+        fn fetch_page(&self, cursor: &Cursor, limit: i32) -> Option<Page> {
+            let offset = match cursor {
+                Cursor::Offset(o) => *o,
+                _ => 0,
+            };
+            let markets = (offset..offset + limit)
+                .map(|i| MarketStatus {
+                    platform: Platform::Manifold,
+                    id: i.to_string(),
+                    prob: 0.5,
+                    url: "url".to_string(),
+                    title: "title".to_string(),
+                    scale: None,
+                    interval: None,
+                    meta: Metadata::default(),
+                })
+                .collect();
+            Some(Page {
+                markets,
+                fetched: limit,
+                next: Some(Cursor::Offset(offset + limit)),
+            })
+        }
+    }
+
+    fn replay() -> Arc<dyn HttpClient> {
+        Arc::new(crate::http::Replay::new(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/http").into(),
+        ))
+    }
+
+    fn no_limit() -> FetchLimit {
+        FetchLimit {
+            total: 3,
+            deadline: Instant::now() + Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn manifold_replay() {
+        let m = Manifold {
+            http: replay(),
+            fetch_limit: no_limit(),
+            workers: 2,
+        };
+        let page = m.fetch_page(&Cursor::Start, 3).expect("page");
+        assert_eq!(page.fetched, 3);
+        assert_eq!(page.next, Some(Cursor::Before("mc1".to_string())));
+        let ms = page.markets;
+        assert_eq!(ms.len(), 4); // one binary, low bettor count, three answers
+        assert_eq!(ms[0].id, "bin1");
+        assert!((ms[0].prob - 0.37).abs() < 1e-6);
+        assert_eq!(ms[1].id, "mc1 0");
+        assert_eq!(
+            ms[1].title,
+            "Who will be the next UK prime minister? Keir Starmer"
+        );
+        assert_eq!(ms[3].id, "mc1 2");
+        assert!((ms[3].prob - 0.2).abs() < 1e-6);
+    }
+    #[test]
+    fn metaculus_replay() {
+        let m = Metaculus {
+            http: replay(),
+            fetch_limit: no_limit(),
+            access_token: "Token abc".to_string(),
+        };
+        let page = m.fetch_page(&Cursor::Start, 6).expect("page");
+        assert_eq!(page.fetched, 6);
+        assert!(matches!(page.next, Some(Cursor::Next(_))));
+        assert_eq!(page.markets.len(), 6);
+    }
+    #[test]
+    fn metaculus_next_limit() {
+        let next = "https://www.metaculus.com/api/posts/?limit=100&offset=100&statuses=open";
+        assert_eq!(
+            with_limit(next, 7),
+            "https://www.metaculus.com/api/posts/?limit=7&offset=100&statuses=open"
+        );
+        assert_eq!(with_limit("https://x.org/p", 3), "https://x.org/p?limit=3");
+    }
+    #[test]
+    fn polymarket_replay() {
+        let p = Polymarket {
+            http: replay(),
+            fetch_limit: no_limit(),
+        };
+        let page = p.fetch_page(&Cursor::Start, 3).expect("page");
+        assert_eq!(page.next, Some(Cursor::Offset(3)));
+        assert_eq!(page.markets.len(), 4);
+        // nothing recorded for the next page
+        assert_eq!(p.fetch_page(&Cursor::Offset(3), 3).map(|p| p.fetched), None);
+    }
+    #[test]
+    fn pagination_limits() {
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let p = Endless {
+            fetch_limit: FetchLimit { total: 5, deadline },
+        };
+        let ms = p.some_markets();
+        assert_eq!(ms.len(), 5); // pages of 2, 2 and 1
+        assert_eq!(ms[4].id, "4");
+        let p = Endless {
+            fetch_limit: FetchLimit {
+                total: 5,
+                deadline: Instant::now(),
+            },
+        };
+        assert!(p.some_markets().is_empty()); // no time left
+    }
+    #[test]
+    fn manifold_numeric() {
+        let o =
+            json::parse(include_str!("../fixtures/manifold_pseudo_numeric.json")).expect("fixture");
+        let m = parse_manifold_numeric(&o, "id".to_string(), "url".to_string(), "t".to_string())
+            .expect("parsed");
+        let scale = m.scale.expect("scale");
+        assert_eq!(scale.zero_point, Some(-1.0));
+        assert!((m.prob - 0.5).abs() < 1e-6); // 99 is halfway on a log scale from 0 to 9999
+        assert!((scale.value(m.prob) - 99.0).abs() < 1e-3);
+        let o = json::parse(include_str!(
+            "../fixtures/manifold_pseudo_numeric_offset.json"
+        ))
+        .expect("fixture");
+        let m = parse_manifold_numeric(&o, "id".to_string(), "url".to_string(), "t".to_string())
+            .expect("parsed");
+        let scale = m.scale.expect("scale");
+        assert_eq!(scale.zero_point, Some(99.0));
+        assert!((m.prob - 0.5).abs() < 1e-6); // the same as the probability Manifold tells
+        assert!((scale.value(m.prob) - 199.0).abs() < 1e-3);
+        let d = json::parse(include_str!("../fixtures/manifold_number.json")).expect("fixture");
+        let m = parse_manifold_number(&d, "id".to_string(), "url".to_string(), "t".to_string())
+            .expect("parsed");
+        assert!((m.scale.expect("scale").value(m.prob) - 2.7).abs() < 1e-3);
+        assert_eq!(bucket_midpoint("-10-0"), Some(-5.0));
+    }
+    #[test]
+    fn metaculus_fixture() {
+        let j = json::parse(include_str!("../fixtures/metaculus_posts.json")).expect("fixture");
+        let ms: Vec<MarketStatus> = j["results"].members().flat_map(parse_metaculus).collect();
+        assert_eq!(ms.len(), 6);
+        assert_eq!(ms[0].id, "3479");
+        assert!((ms[0].prob - 0.25).abs() < 1e-6);
+        assert_eq!(ms[1].id, "27891 0");
+        assert_eq!(
+            ms[1].title,
+            "Which party will win the 2026 German state election in Saxony-Anhalt? AfD"
+        );
+        assert_eq!(ms[3].id, "27891 2");
+        let date = ms[4].scale.as_ref().expect("scale");
+        assert_eq!(date.kind, ScaleKind::Date);
+        assert!((date.value(ms[4].prob) - 1893456000.0).abs() < 1.0); // 2030-01-01
+        let gdp = ms[5].scale.as_ref().expect("scale");
+        assert_eq!(gdp.kind, ScaleKind::Median);
+        assert!((gdp.value(ms[5].prob) - 100.0).abs() < 1e-3); // halfway on log scale
+        let (lower, upper) = ms[5].interval.expect("interval");
+        assert!(lower < ms[5].prob && ms[5].prob < upper);
+    }
+    #[test]
+    fn polymarket_fixture() {
+        let j = json::parse(include_str!("../fixtures/polymarket_events.json")).expect("fixture");
+        let ms: Vec<MarketStatus> = j.members().flat_map(parse_polymarket_event).collect();
+        assert_eq!(ms.len(), 4);
+        assert_eq!(ms[0].id, "fed-decision-in-december");
+        assert_eq!(ms[0].title, "Fed decision in December?");
+        assert_eq!(
+            ms[0].url,
+            "https://polymarket.com/event/fed-decision-in-december/fed-decision-in-december"
+        );
+        assert_eq!(ms[1].id, "nyc-mayor-2025 will-zohran-mamdani-win-nyc-mayor");
+        assert_eq!(ms[1].title, "NYC Mayor 2025 Zohran Mamdani");
+        assert!((ms[1].prob - 0.915).abs() < 1e-6);
+        assert_eq!(ms[2].id, "nyc-mayor-2025 will-andrew-cuomo-win-nyc-mayor");
+        assert_eq!(ms[3].id, "lol-t1-vs-gen");
+        assert_eq!(ms[3].title, "LoL: T1 vs Gen.G T1");
+    }
+    #[test]
+    fn kalshi_fixture() {
+        let j = json::parse(include_str!("../fixtures/kalshi_markets.json")).expect("fixture");
+        let ms: Vec<MarketStatus> = j["markets"].members().filter_map(parse_kalshi).collect();
+        assert_eq!(ms.len(), 3);
+        assert_eq!(ms[0].id, "KXRECSSNBER-26");
+        assert_eq!(ms[0].title, "Recession in 2026?");
+        assert!((ms[0].prob - 0.235).abs() < 1e-6);
+        assert_eq!(ms[0].url, "https://kalshi.com/markets/kxrecssnber");
+        assert_eq!(ms[0].meta.volume, Some(2514032.0));
+        assert_eq!(ms[0].meta.liquidity, Some(154802.13));
+        assert_eq!(
+            ms[0].meta.close_time.map(|t| t.to_rfc3339()),
+            Some("2027-01-01T04:59:00+00:00".to_string())
+        );
+        assert_eq!(ms[0].meta.outcome_type.as_deref(), Some("binary"));
+        assert_eq!(ms[1].id, "KXFEDDECISION-26JAN H0");
+        assert_eq!(ms[1].title, "Fed decision in Jan 2026? Hold");
+        assert!((ms[1].prob - 0.81).abs() < 1e-6);
+        assert_eq!(ms[2].id, "KXFEDDECISION-26JAN C25");
+        // low volume, missing quotes and sports markets are dropped
+        assert!(ms.iter().all(|m| !m.id.contains("H25")));
+    }
+    #[test]
+    fn predictit_fixture() {
+        let j = json::parse(include_str!("../fixtures/predictit_all.json")).expect("fixture");
+        let ms: Vec<MarketStatus> = j["markets"].members().flat_map(parse_predictit).collect();
+        assert_eq!(ms.len(), 3);
+        assert_eq!(ms[0].id, "8069 32180");
+        assert_eq!(
+            ms[0].title,
+            "Which party will win the 2026 Senate? Republican"
+        );
+        assert!((ms[0].prob - 0.68).abs() < 1e-6);
+        assert_eq!(ms[1].id, "8069 32181");
+        assert_eq!(ms[2].id, "8112 32307");
+        assert_eq!(ms[2].title, "Will Congress pass a budget by Oct 1?");
+    }
+    #[test]
+    fn cultivate_fixture() {
+        let j = json::parse(include_str!("../fixtures/gjopen_questions.json")).expect("fixture");
+        let ms: Vec<MarketStatus> = j["questions"]
+            .members()
+            .flat_map(|o| parse_cultivate(Platform::GJOpen, o))
+            .collect();
+        assert_eq!(ms.len(), 4);
+        assert_eq!(ms[0].id, "4012");
+        assert!((ms[0].prob - 0.14).abs() < 1e-6);
+        assert_eq!(ms[0].url, "https://www.gjopen.com/questions/4012");
+        assert_eq!(ms[0].meta.bettors, Some(212));
+        assert!(ms[0].meta.close_time.is_some());
+        assert_eq!(ms[1].id, "4020 18811");
+        assert_eq!(
+            ms[1].title,
+            "Who will win the 2026 Colombian presidential election? Iván Cepeda"
+        );
+        assert_eq!(ms[3].id, "4020 18813");
+    }
+    #[test]
+    fn infer_fixture() {
+        let j = json::parse(include_str!("../fixtures/infer_questions.json")).expect("fixture");
+        let ms = parse_cultivate(Platform::Infer, &j["questions"][0]);
+        assert_eq!(ms.len(), 1);
+        assert_eq!(ms[0].platform, Platform::Infer);
+        assert!((ms[0].prob - 0.62).abs() < 1e-6);
+        assert_eq!(
+            ms[0].url,
+            "https://www.randforecastinginitiative.org/questions/1702"
+        );
+    }
+}
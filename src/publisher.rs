@@ -0,0 +1,56 @@
+use crate::http::HttpError;
+use crate::model::Change;
+
+/// A network where noteworthy changes get posted
+pub trait Publisher {
+    /// Name of the config section, also used in the log
+    fn target(&self) -> &str;
+
+    /// Returns the id of the new post if the target tells one
+    fn publish(&self, change: &Change) -> Result<Option<String>, HttpError>;
+
+    /// Whether posts can be updated when their market moves again
+    fn can_edit(&self) -> bool {
+        false
+    }
+
+    /// Replace the post with this receipt by a newer change of the same market.
+    /// Returns false if the post already told it.
+    fn edit(&self, _receipt: &str, _change: &Change) -> Result<bool, HttpError> {
+        Err(HttpError::Transport(format!(
+            "{} cannot edit",
+            self.target()
+        )))
+    }
+}
+
+/// Shorten text to at most limit characters.
+/// The last line with link and hashtags is kept, the text before gets cut.
+pub fn fit(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+    let cut = |s: &str, n: usize| s.chars().take(n).collect::<String>() + "…";
+    match text.rsplit_once('\n') {
+        Some((head, tail)) if tail.chars().count() + 2 < limit => {
+            let room = limit - tail.chars().count() - 2;
+            format!("{}\n{}", cut(head.trim_end(), room), tail)
+        }
+        _ => cut(text, limit.saturating_sub(1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_limits() {
+        let text = "+25% in a day 📈 Will it rain?\nhttps://x.org/a #prediction #Kalshi";
+        assert_eq!(fit(text, 500), text);
+        let short = fit(text, 50);
+        assert_eq!(short.chars().count(), 50);
+        assert_eq!(short, "+25% in a day…\nhttps://x.org/a #prediction #Kalshi");
+        assert_eq!(fit(text, 10), "+25% in a…");
+    }
+}
@@ -0,0 +1,187 @@
+use crate::model::{Change, Scoring};
+
+/// Orders candidate changes by noteworthiness, the first one gets published.
+/// Changes which should not be published at all are left out.
+pub trait RankingStrategy {
+    fn rank(&self, candidates: Vec<Change>) -> Vec<Change>;
+}
+
+/// Smallest move worth a post, in the units of each scoring
+#[derive(Clone, Debug, PartialEq)]
+pub struct MinMove {
+    /// Probability step
+    pub absolute: f32,
+    /// Multiple of the usual hourly move
+    pub volatility: f32,
+    /// Shift in log-odds, 1.0 is odds changing about 2.7×
+    pub log_odds: f32,
+}
+
+impl MinMove {
+    pub fn of(&self, scoring: Scoring) -> f32 {
+        match scoring {
+            Scoring::Absolute => self.absolute,
+            Scoring::Volatility => self.volatility,
+            Scoring::LogOdds => self.log_odds,
+        }
+    }
+
+    pub fn set(&mut self, scoring: Scoring, value: f32) {
+        match scoring {
+            Scoring::Absolute => self.absolute = value,
+            Scoring::Volatility => self.volatility = value,
+            Scoring::LogOdds => self.log_odds = value,
+        }
+    }
+
+    /// Whether the change moved enough the way this scoring sees it
+    fn passed_by(&self, c: &Change, scoring: Scoring) -> bool {
+        let scoring = c.judged_by(scoring);
+        c.moved(scoring) >= self.of(scoring)
+    }
+}
+
+impl Default for MinMove {
+    fn default() -> Self {
+        MinMove {
+            absolute: 0.2,
+            volatility: 3.0,
+            log_odds: 1.0,
+        }
+    }
+}
+
+/// Ranks by a single scoring, nothing is news unless the best one moved enough
+pub struct ScoredRanking {
+    pub scoring: Scoring,
+    pub min_move: MinMove,
+    /// Leave out changes which did not move enough before ranking,
+    /// so a smaller move of another market can still be told
+    pub skip_small: bool,
+}
+
+impl Default for ScoredRanking {
+    fn default() -> Self {
+        ScoredRanking {
+            scoring: Scoring::Absolute,
+            min_move: MinMove::default(),
+            skip_small: false,
+        }
+    }
+}
+
+impl RankingStrategy for ScoredRanking {
+    fn rank(&self, candidates: Vec<Change>) -> Vec<Change> {
+        let mut ranked: Vec<Change> = candidates
+            .into_iter()
+            .filter(|c| !self.skip_small || self.min_move.passed_by(c, self.scoring))
+            .collect();
+        ranked.sort_by(|a, b| b.score(self.scoring).total_cmp(&a.score(self.scoring)));
+        ranked
+            .into_iter()
+            .take_while(|c| self.min_move.passed_by(c, self.scoring))
+            .map(|c| c.told_by(self.scoring))
+            .collect()
+    }
+}
+
+/// Ranks by a weighted sum of scorings.
+/// A change is told the way of the scoring which contributed most,
+/// and has to move enough in the units of that scoring.
+pub struct BlendedRanking {
+    pub weights: Vec<(Scoring, f32)>,
+    pub min_move: MinMove,
+}
+
+impl BlendedRanking {
+    fn score(&self, c: &Change) -> f32 {
+        self.weights.iter().map(|(s, w)| w * c.score(*s)).sum()
+    }
+
+    fn dominant(&self, c: &Change) -> Scoring {
+        self.weights
+            .iter()
+            .map(|(s, w)| (*s, w * c.score(*s)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(s, _)| s)
+            .unwrap_or(Scoring::Absolute)
+    }
+}
+
+impl RankingStrategy for BlendedRanking {
+    fn rank(&self, candidates: Vec<Change>) -> Vec<Change> {
+        let mut ranked: Vec<Change> = candidates
+            .into_iter()
+            .filter(|c| self.min_move.passed_by(c, self.dominant(c)))
+            .collect();
+        ranked.sort_by(|a, b| self.score(b).total_cmp(&self.score(a)));
+        ranked
+            .into_iter()
+            .map(|c| {
+                let scoring = self.dominant(&c);
+                c.told_by(scoring)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::DiffDuration;
+
+    #[test]
+    fn scored_like_before() {
+        let hour = Change::new_from05(DiffDuration::hour(), 0.65);
+        let day = Change::new_from05(DiffDuration::day(), 0.75);
+        let week = Change::new_from05(DiffDuration::week(), 0.9);
+        let ranked = ScoredRanking::default().rank(vec![week.clone(), hour, day.clone()]);
+        // the 15% hour move scores best but is too small, so nothing at all
+        assert!(ranked.is_empty());
+        let ranked = ScoredRanking::default().rank(vec![week.clone(), day.clone()]);
+        assert_eq!(ranked, vec![day, week]);
+    }
+
+    #[test]
+    fn scored_skipping_small() {
+        let hour = Change::new_from05(DiffDuration::hour(), 0.65);
+        let day = Change::new_from05(DiffDuration::day(), 0.75);
+        let ranking = ScoredRanking {
+            skip_small: true,
+            ..ScoredRanking::default()
+        };
+        // the hour move is left out, so the day move gets told
+        assert_eq!(ranking.rank(vec![hour, day.clone()]), vec![day]);
+    }
+
+    #[test]
+    fn tail_move_in_log_odds() {
+        let tail = Change::new_from(DiffDuration::day(), 0.02, 0.12);
+        let mid = Change::new_from(DiffDuration::day(), 0.5, 0.6);
+        let ranking = ScoredRanking {
+            scoring: Scoring::LogOdds,
+            ..ScoredRanking::default()
+        };
+        // only ten points either way, but the tail odds grew more than six times
+        let ranked = ranking.rank(vec![mid.clone(), tail.clone()]);
+        assert_eq!(ranked, vec![tail.clone().told_by(Scoring::LogOdds)]);
+        // as probability steps neither is big enough
+        assert!(ScoredRanking::default().rank(vec![mid, tail]).is_empty());
+    }
+
+    #[test]
+    fn blended() {
+        let tail = Change::new_from(DiffDuration::day(), 0.05, 0.3);
+        let mid = Change::new_from05(DiffDuration::day(), 0.75);
+        let small = Change::new_from05(DiffDuration::hour(), 0.6);
+        let blend = BlendedRanking {
+            weights: vec![(Scoring::Absolute, 1.0), (Scoring::LogOdds, 1.0)],
+            min_move: MinMove::default(),
+        };
+        let ranked = blend.rank(vec![mid, small, tail]);
+        assert_eq!(ranked.len(), 2);
+        // 5% to 30% beats 50% to 75%, both are told in odds
+        assert!(ranked[0].to_string().starts_with("odds up 8×"));
+        assert!(ranked[1].to_string().starts_with("odds up 3×"));
+    }
+}
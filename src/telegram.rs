@@ -0,0 +1,182 @@
+use crate::http::{HttpClient, HttpError, Request};
+use crate::model::Change;
+use crate::publisher::{fit, Publisher};
+use json::{object, JsonValue};
+use log::*;
+use std::sync::Arc;
+
+/// Posts to a channel through the Telegram Bot API
+pub struct Telegram {
+    http: Arc<dyn HttpClient>,
+    name: String,
+    /// Like https://api.telegram.org
+    endpoint: String,
+    bot_token: String,
+    /// Like @channelname
+    chat_id: String,
+    /// Longest message Telegram accepts, in characters
+    max_length: usize,
+    /// Update the last message instead of staying quiet when its market moves again
+    edit: bool,
+}
+
+impl Telegram {
+    pub fn new(
+        http: Arc<dyn HttpClient>,
+        name: String,
+        endpoint: String,
+        bot_token: String,
+        chat_id: String,
+        max_length: usize,
+        edit: bool,
+    ) -> Self {
+        Telegram {
+            http,
+            name,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bot_token,
+            chat_id,
+            max_length,
+            edit,
+        }
+    }
+
+    /// Returns the result of the method
+    fn call(&self, method: &str, params: JsonValue) -> Result<JsonValue, HttpError> {
+        let url = format!("{}/bot{}/{}", self.endpoint, self.bot_token, method);
+        let request = Request::post(url.as_str())
+            .header("Accept", "application/json")
+            .json(&params);
+        let response = self.http.send(&request)?;
+        let mut j = json::parse(response.body.as_str())
+            .map_err(|e| HttpError::Transport(format!("bad answer to {}: {}", method, e)))?;
+        Ok(j["result"].take())
+    }
+
+    /// Message text with bold title linking to the market
+    fn html(&self, change: &Change) -> String {
+        let text = fit(&change.to_string(), self.max_length);
+        let (headline, tail) = text.rsplit_once('\n').unwrap_or((&text, ""));
+        let title = escape(change.title());
+        let headline = escape(headline);
+        if title.is_empty() || !headline.contains(&title) {
+            return format!("{}\n{}", headline, escape(tail));
+        }
+        let link = format!("<b><a href=\"{}\">{}</a></b>", escape(change.url()), title);
+        // the title links to the market already
+        let tags = tail.replacen(change.url(), "", 1);
+        format!(
+            "{}\n{}",
+            headline.replacen(&title, &link, 1),
+            escape(tags.trim())
+        )
+    }
+}
+
+impl Publisher for Telegram {
+    fn target(&self) -> &str {
+        &self.name
+    }
+
+    fn publish(&self, change: &Change) -> Result<Option<String>, HttpError> {
+        let message = self.call(
+            "sendMessage",
+            object! {
+                chat_id: self.chat_id.as_str(),
+                text: self.html(change),
+                parse_mode: "HTML",
+            },
+        )?;
+        Ok(message["message_id"].as_i64().map(|id| id.to_string()))
+    }
+
+    fn can_edit(&self) -> bool {
+        self.edit
+    }
+
+    fn edit(&self, receipt: &str, change: &Change) -> Result<bool, HttpError> {
+        let Ok(message_id) = receipt.parse::<i64>() else {
+            return Err(HttpError::Transport(format!("bad message id {}", receipt)));
+        };
+        let params = object! {
+            chat_id: self.chat_id.as_str(),
+            message_id: message_id,
+            text: self.html(change),
+            parse_mode: "HTML",
+        };
+        match self.call("editMessageText", params) {
+            Ok(_message) => Ok(true),
+            Err(HttpError::Status(r)) if r.body.contains("message is not modified") => {
+                debug!("message {} is up to date", message_id);
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Telegram only knows these HTML entities
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock;
+    use crate::http::UreqClient;
+    use crate::model::DiffDuration;
+
+    #[test]
+    fn send_and_edit() {
+        let (address, requests) = mock::serve(vec![
+            (200, r#"{"ok": true, "result": {"message_id": 42}}"#),
+            (200, r#"{"ok": true, "result": {"message_id": 42}}"#),
+            (
+                400,
+                r#"{"ok": false, "description": "Bad Request: message is not modified"}"#,
+            ),
+            (
+                400,
+                r#"{"ok": false, "description": "Bad Request: chat not found"}"#,
+            ),
+        ]);
+        let telegram = Telegram::new(
+            Arc::new(UreqClient::new()),
+            "telegram".to_string(),
+            address,
+            "123:abc".to_string(),
+            "@news".to_string(),
+            4096,
+            true,
+        );
+        let change = Change::new_from05(DiffDuration::day(), 0.75);
+        let receipt = telegram.publish(&change).expect("send");
+        assert_eq!(receipt.as_deref(), Some("42"));
+        let (path, message) = requests.recv().expect("send");
+        assert_eq!(path, "/bot123:abc/sendMessage");
+        assert_eq!(message["chat_id"], "@news");
+        assert_eq!(message["parse_mode"], "HTML");
+        assert_eq!(
+            message["text"],
+            "+25% in a day 📈 <b><a href=\"url\">title</a></b>\n#prediction #platform"
+        );
+        let moved = Change::new_from05(DiffDuration::day(), 0.9);
+        assert!(telegram.edit("42", &moved).expect("edit"));
+        let (path, message) = requests.recv().expect("edit");
+        assert_eq!(path, "/bot123:abc/editMessageText");
+        assert_eq!(message["message_id"], 42);
+        assert!(message["text"].as_str().expect("text").starts_with("+40%"));
+        assert!(!telegram.edit("42", &moved).expect("same text again"));
+        assert!(telegram.edit("42", &moved).is_err());
+        assert!(telegram.edit("not a number", &moved).is_err());
+    }
+
+    #[test]
+    fn escaped() {
+        assert_eq!(escape("a < b & \"c\""), "a &lt; b &amp; &quot;c&quot;");
+    }
+}